@@ -0,0 +1,709 @@
+use {
+	crate::{
+		commands,
+		config::EnvProfile,
+		create_missing_token_accounts, ensure_fee_within, ensure_tokenizer_active,
+		ensure_underlying_balance, ensure_within_operation_cap, mint_token_program,
+		output::Report,
+		pda::{get_principal_mint_address, get_yield_mint_address},
+		rpc::RpcProvider,
+		send_with_retries, simulate, InstructionCommonFields, TransactionOptions,
+	},
+	anyhow::{anyhow, Result},
+	solana_program::{instruction::Instruction, pubkey::Pubkey},
+	solana_sdk::{
+		packet::PACKET_DATA_SIZE,
+		signature::{Signature, Signer},
+		transaction::Transaction,
+	},
+	std::{
+		collections::BTreeMap,
+		fs::{self, OpenOptions},
+		io::{self, Write},
+		str::FromStr,
+		time::Instant,
+	},
+};
+
+const HEADER: &str = "tokenizer,amount,underlying_mint";
+
+const CHECKPOINT_HEADER: &str = "line,tokenizer,amount,underlying_mint,signature";
+
+/// The most signatures `getSignatureStatuses` accepts in a single request.
+const MAX_SIGNATURE_STATUSES: usize = 256;
+
+/// Checks and settings applied to every row of a batch, on top of how its transactions are sent.
+pub struct BatchOptions<'a> {
+	pub max_operation_amount: Option<u64>,
+	pub env_profile: &'a EnvProfile,
+	/// Assume underlying mints that cannot be read belong to Token-2022
+	pub token_2022: bool,
+	/// Skip the expiry check of each row's tokenizer
+	pub force: bool,
+	/// Current time the tokenizer expiries are checked against
+	pub now: i64,
+	/// File recording the rows sent, so a rerun skips the rows that landed
+	pub checkpoint: Option<&'a str>,
+	/// Report the totals of the run once it finishes
+	pub output_summary: bool,
+}
+
+/// Totals of a batch run, reported with `--output-summary`.
+#[derive(Default)]
+struct BatchSummary {
+	total_rows: usize,
+	succeeded: usize,
+	failed: usize,
+	skipped: usize,
+	/// Base units tokenized by the rows that succeeded, summed across underlying mints
+	amount: u128,
+	/// Lamports paid for the transactions sent
+	fees: u64,
+}
+
+impl BatchSummary {
+	fn report(&self, started: Instant, report: &mut Report) {
+		report.field("Total Rows", self.total_rows);
+		report.field("Succeeded", self.succeeded);
+		report.field("Failed", self.failed);
+		report.field("Skipped", self.skipped);
+		report.field("Total Amount", self.amount);
+		report.field("Fees Paid", format_args!("{} lamports", self.fees));
+		report.field(
+			"Elapsed",
+			format_args!("{:.1}s", started.elapsed().as_secs_f64()),
+		);
+	}
+}
+
+struct BatchRow {
+	line: usize,
+	tokenizer: Pubkey,
+	amount: u64,
+	underlying_mint: Pubkey,
+}
+
+impl BatchRow {
+	/// The columns of the row in a normalized form, recorded in the checkpoint to detect a batch
+	/// file edited between runs.
+	fn columns(&self) -> String {
+		format!(
+			"{},{},{}",
+			self.tokenizer, self.amount, self.underlying_mint
+		)
+	}
+}
+
+fn parse_row(line: usize, contents: &str) -> Result<BatchRow> {
+	let columns: Vec<&str> = contents.split(',').map(str::trim).collect();
+	let [tokenizer, amount, underlying_mint] = columns[..] else {
+		return Err(anyhow!(
+			"Line {}: expected 3 columns, got {}",
+			line,
+			columns.len()
+		));
+	};
+
+	let row = BatchRow {
+		line,
+		tokenizer: Pubkey::from_str(tokenizer)
+			.map_err(|err| anyhow!("Line {}: invalid tokenizer `{}`: {}", line, tokenizer, err))?,
+		amount: amount
+			.parse()
+			.map_err(|err| anyhow!("Line {}: invalid amount `{}`: {}", line, amount, err))?,
+		underlying_mint: Pubkey::from_str(underlying_mint).map_err(|err| {
+			anyhow!(
+				"Line {}: invalid underlying mint `{}`: {}",
+				line,
+				underlying_mint,
+				err
+			)
+		})?,
+	};
+
+	if row.amount == 0 {
+		return Err(anyhow!("Line {}: amount must be greater than zero", line));
+	}
+
+	Ok(row)
+}
+
+fn parse_batch_file(path: &str) -> Result<Vec<BatchRow>> {
+	let contents = fs::read_to_string(path)
+		.map_err(|err| anyhow!("Unable to read batch file {}: {}", path, err))?;
+
+	parse_batch(path, &contents)
+}
+
+/// Parses the `contents` of the batch file at `path`, which must start with the
+/// `tokenizer,amount,underlying_mint` header. Blank lines are skipped but still counted, so rows
+/// keep the line numbers they have in the file.
+fn parse_batch(path: &str, contents: &str) -> Result<Vec<BatchRow>> {
+	let mut lines = contents.lines().enumerate();
+	match lines.next() {
+		Some((_, header)) if header.trim() == HEADER => {}
+		_ => {
+			return Err(anyhow!(
+				"Batch file {} must start with the header `{}`",
+				path,
+				HEADER
+			))
+		}
+	}
+
+	lines
+		.filter(|(_, contents)| !contents.trim().is_empty())
+		.map(|(index, contents)| parse_row(index + 1, contents))
+		.collect()
+}
+
+/// Reads the signature each row of the checkpoint file at `path` was sent in, keyed by line. A
+/// missing file is an empty checkpoint.
+fn read_checkpoint(path: &str, rows: &[BatchRow]) -> Result<BTreeMap<usize, Signature>> {
+	match fs::read_to_string(path) {
+		Ok(contents) => parse_checkpoint(path, &contents, rows),
+		Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+		Err(err) => Err(anyhow!("Unable to read checkpoint file {}: {}", path, err)),
+	}
+}
+
+/// Parses the `contents` of the checkpoint file at `path`. Every recorded row must still be in
+/// `rows` unchanged, so a checkpoint is never applied to a different batch file.
+fn parse_checkpoint(
+	path: &str,
+	contents: &str,
+	rows: &[BatchRow],
+) -> Result<BTreeMap<usize, Signature>> {
+	let mut lines = contents.lines();
+	match lines.next() {
+		None => return Ok(BTreeMap::new()),
+		Some(header) if header.trim() == CHECKPOINT_HEADER => {}
+		Some(_) => {
+			return Err(anyhow!(
+				"Checkpoint file {} must start with the header `{}`",
+				path,
+				CHECKPOINT_HEADER
+			))
+		}
+	}
+
+	let mut signatures = BTreeMap::new();
+	for entry in lines.filter(|entry| !entry.trim().is_empty()) {
+		let (line, columns, signature) = entry
+			.split_once(',')
+			.and_then(|(line, rest)| {
+				let (columns, signature) = rest.rsplit_once(',')?;
+				Some((line.trim(), columns.trim(), signature.trim()))
+			})
+			.ok_or_else(|| anyhow!("Invalid checkpoint entry `{}` in {}", entry, path))?;
+
+		let line: usize = line.parse().map_err(|err| {
+			anyhow!(
+				"Invalid line `{}` in checkpoint file {}: {}",
+				line,
+				path,
+				err
+			)
+		})?;
+		let row = rows.iter().find(|row| row.line == line).ok_or_else(|| {
+			anyhow!(
+				"Checkpoint file {} records line {}, which is not a row of the batch file",
+				path,
+				line
+			)
+		})?;
+		if columns != row.columns() {
+			return Err(anyhow!(
+				"Line {} differs from the row recorded in checkpoint file {}, remove the \
+				 checkpoint to start the batch over",
+				line,
+				path
+			));
+		}
+
+		let signature = Signature::from_str(signature).map_err(|err| {
+			anyhow!(
+				"Invalid signature `{}` in checkpoint file {}: {}",
+				signature,
+				path,
+				err
+			)
+		})?;
+		signatures.insert(line, signature);
+	}
+
+	Ok(signatures)
+}
+
+/// Appends `rows`, sent in the transaction `signature`, to the checkpoint file at `path`,
+/// writing the header first when the file is new.
+fn append_checkpoint(path: &str, rows: &[&BatchRow], signature: &Signature) -> Result<()> {
+	let mut file = OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(path)
+		.map_err(|err| anyhow!("Unable to open checkpoint file {}: {}", path, err))?;
+
+	let mut entries = String::new();
+	if file
+		.metadata()
+		.map(|metadata| metadata.len() == 0)
+		.unwrap_or(false)
+	{
+		entries.push_str(CHECKPOINT_HEADER);
+		entries.push('\n');
+	}
+	for row in rows {
+		entries.push_str(&format!("{},{},{}\n", row.line, row.columns(), signature));
+	}
+
+	file.write_all(entries.as_bytes())
+		.map_err(|err| anyhow!("Unable to write checkpoint file {}: {}", path, err))
+}
+
+/// Returns which of the `recorded` signatures landed without error at the client's commitment.
+fn landed_signatures(
+	client: &impl RpcProvider,
+	recorded: &BTreeMap<usize, Signature>,
+) -> Result<Vec<Signature>> {
+	// Rows sent together share a signature
+	let mut signatures: Vec<Signature> = Vec::new();
+	for signature in recorded.values() {
+		if !signatures.contains(signature) {
+			signatures.push(*signature);
+		}
+	}
+
+	let mut landed = Vec::new();
+	for chunk in signatures.chunks(MAX_SIGNATURE_STATUSES) {
+		let statuses = client
+			.get_signature_statuses(chunk)
+			.map_err(|err| anyhow!("Unable to fetch checkpoint signature statuses: {}", err))?
+			.value;
+
+		for (signature, status) in chunk.iter().zip(statuses) {
+			if status.is_some_and(|status| {
+				status.err.is_none() && status.satisfies_commitment(client.commitment())
+			}) {
+				landed.push(*signature);
+			}
+		}
+	}
+
+	Ok(landed)
+}
+
+fn tokenize_instruction(row: &BatchRow, ctx: &commands::Context) -> Result<Instruction> {
+	commands::build_deposit_and_tokenize(
+		ctx,
+		&InstructionCommonFields::new(row.tokenizer, row.underlying_mint, row.amount),
+		row.amount,
+	)
+	.map_err(|err| anyhow!("Line {}: {}", row.line, err))
+}
+
+pub fn fits_in_transaction(instructions: &[Instruction], payer: &Pubkey) -> bool {
+	let transaction = Transaction::new_with_payer(instructions, Some(payer));
+	bincode::serialized_size(&transaction)
+		.map(|size| size as usize <= PACKET_DATA_SIZE)
+		.unwrap_or(false)
+}
+
+/// Deposits and tokenizes every row of the batch file at `path`, packing as many rows into
+/// each transaction as fit and reporting the outcome of each row. The first of `signers` owns
+/// the deposited tokens, `fee_payer` pays for the transactions and any token accounts created.
+/// Each row is held to the same operation cap, expiry and balance checks as a single tokenize.
+/// With a checkpoint, rows recorded by an earlier run are skipped once their transaction is seen
+/// to have landed and sent again otherwise. The summary, when requested, is reported even when
+/// rows failed.
+pub fn tokenize_batch(
+	client: &impl RpcProvider,
+	options: &TransactionOptions,
+	batch_options: &BatchOptions,
+	signers: &[&dyn Signer],
+	fee_payer: &Pubkey,
+	path: &str,
+	report: &mut Report,
+) -> Result<()> {
+	let started = Instant::now();
+
+	let mut rows = parse_batch_file(path)?;
+	if rows.is_empty() {
+		return Err(anyhow!("Batch file {} has no rows", path));
+	}
+
+	let mut summary = BatchSummary {
+		total_rows: rows.len(),
+		..BatchSummary::default()
+	};
+
+	if let Some(checkpoint) = batch_options.checkpoint {
+		let recorded = read_checkpoint(checkpoint, &rows)?;
+		let landed = landed_signatures(client, &recorded)?;
+
+		rows.retain(|row| match recorded.get(&row.line) {
+			Some(signature) if landed.contains(signature) => {
+				report.field(
+					&format!("Line {}", row.line),
+					format_args!("skipped, sent in {}", signature),
+				);
+				summary.skipped += 1;
+				false
+			}
+			Some(signature) => {
+				eprintln!(
+					"Warning: line {} was sent in {}, which did not land, sending it again",
+					row.line, signature
+				);
+				true
+			}
+			None => true,
+		});
+
+		if rows.is_empty() {
+			if batch_options.output_summary {
+				summary.report(started, report);
+			}
+			return Ok(());
+		}
+	}
+
+	let authority = signers[0].pubkey();
+
+	let mut underlying_token_programs: BTreeMap<Pubkey, Pubkey> = BTreeMap::new();
+	let mut underlying_totals: BTreeMap<Pubkey, u64> = BTreeMap::new();
+
+	// Every row is checked before anything is sent so a bad row cannot leave the batch half
+	// applied
+	for row in &rows {
+		ensure_within_operation_cap(
+			batch_options.max_operation_amount,
+			batch_options.env_profile,
+			&row.underlying_mint,
+			row.amount,
+		)
+		.map_err(|err| anyhow!("Line {}: {}", row.line, err))?;
+
+		if !batch_options.force {
+			ensure_tokenizer_active(client, &row.tokenizer, batch_options.now)
+				.map_err(|err| anyhow!("Line {}: {}", row.line, err))?;
+		}
+
+		if !underlying_token_programs.contains_key(&row.underlying_mint) {
+			let token_program =
+				mint_token_program(client, &row.underlying_mint, batch_options.token_2022)?;
+			underlying_token_programs.insert(row.underlying_mint, token_program);
+		}
+
+		let total = underlying_totals.entry(row.underlying_mint).or_default();
+		*total = total.checked_add(row.amount).ok_or_else(|| {
+			anyhow!(
+				"Line {}: total amount of underlying mint {} overflows",
+				row.line,
+				row.underlying_mint
+			)
+		})?;
+	}
+
+	// Rows draw on the same token account per underlying mint, so it must cover their sum
+	if !options.dry_run {
+		for (underlying_mint, total) in &underlying_totals {
+			let source = spl_associated_token_account::get_associated_token_address_with_program_id(
+				&authority,
+				underlying_mint,
+				&underlying_token_programs[underlying_mint],
+			);
+			ensure_underlying_balance(client, &source, *total)
+				.map_err(|err| anyhow!("Rows of underlying mint {}: {}", underlying_mint, err))?;
+		}
+	}
+
+	let mut mints: Vec<(Pubkey, Pubkey)> = rows
+		.iter()
+		.flat_map(|row| {
+			[
+				(
+					get_principal_mint_address(&row.tokenizer, &crate::program_id()).0,
+					spl_token::id(),
+				),
+				(
+					get_yield_mint_address(&row.tokenizer, &crate::program_id()).0,
+					spl_token::id(),
+				),
+			]
+		})
+		.collect();
+	mints.sort();
+	mints.dedup();
+
+	// Each entry pairs an instruction with the line of the row it tokenizes, account creation
+	// instructions belong to no row
+	let mut entries: Vec<(Option<usize>, Instruction)> =
+		create_missing_token_accounts(client, fee_payer, &authority, &mints)?
+			.into_iter()
+			.map(|instruction| (None, instruction))
+			.collect();
+	for row in &rows {
+		let ctx = commands::Context {
+			program_id: crate::program_id(),
+			authority,
+			underlying_token_program: underlying_token_programs[&row.underlying_mint],
+		};
+		entries.push((Some(row.line), tokenize_instruction(row, &ctx)?));
+	}
+
+	let mut chunks: Vec<Vec<(Option<usize>, Instruction)>> = vec![Vec::new()];
+	for entry in entries {
+		let chunk = chunks.last_mut().expect("chunks is never empty");
+		let mut instructions: Vec<Instruction> = chunk.iter().map(|(_, ix)| ix.clone()).collect();
+		instructions.push(entry.1.clone());
+		options.wrap(fee_payer, &mut instructions);
+
+		if chunk.is_empty() || fits_in_transaction(&instructions, fee_payer) {
+			chunk.push(entry);
+		} else {
+			chunks.push(vec![entry]);
+		}
+	}
+
+	for chunk in chunks {
+		let lines: Vec<usize> = chunk.iter().filter_map(|(line, _)| *line).collect();
+		let chunk_rows: Vec<&BatchRow> = rows
+			.iter()
+			.filter(|row| lines.contains(&row.line))
+			.collect();
+		let mut instructions: Vec<Instruction> = chunk.into_iter().map(|(_, ix)| ix).collect();
+		options.wrap(fee_payer, &mut instructions);
+
+		match send_chunk(client, options, &instructions, signers, fee_payer, report) {
+			Ok(sent) => {
+				summary.succeeded += chunk_rows.len();
+				summary.amount += chunk_rows
+					.iter()
+					.map(|row| row.amount as u128)
+					.sum::<u128>();
+
+				let Some((signature, fee)) = sent else {
+					for line in lines {
+						report.field(&format!("Line {}", line), "simulated");
+					}
+					continue;
+				};
+
+				summary.fees += fee;
+				for line in &lines {
+					report.field(&format!("Line {}", line), signature);
+				}
+
+				// A checkpoint that cannot be written stops the batch, the rows it misses would be
+				// sent again by the next run
+				if let Some(checkpoint) = batch_options.checkpoint {
+					append_checkpoint(checkpoint, &chunk_rows, &signature)?;
+				}
+			}
+			Err(err) => {
+				summary.failed += chunk_rows.len();
+				for line in lines {
+					report.field(&format!("Line {}", line), format_args!("failed: {}", err));
+				}
+			}
+		}
+	}
+
+	if batch_options.output_summary {
+		summary.report(started, report);
+	}
+
+	if summary.failed > 0 {
+		return Err(anyhow!(
+			"{} of {} rows failed to tokenize",
+			summary.failed,
+			rows.len()
+		));
+	}
+
+	Ok(())
+}
+
+/// Simulates the chunk when `--dry-run` is set, otherwise checks its fee against `--max-fee` and
+/// sends it, returning the signature and the fee paid. Nothing is sent, and nothing returned, when
+/// simulating.
+fn send_chunk(
+	client: &impl RpcProvider,
+	options: &TransactionOptions,
+	instructions: &[Instruction],
+	signers: &[&dyn Signer],
+	fee_payer: &Pubkey,
+	report: &mut Report,
+) -> Result<Option<(Signature, u64)>> {
+	let mut transaction = Transaction::new_with_payer(instructions, Some(fee_payer));
+
+	if options.dry_run || options.simulate_at_slot.is_some() {
+		simulate(client, &transaction, options.simulate_at_slot, report)?;
+		return Ok(None);
+	}
+
+	let blockhash = client
+		.get_latest_blockhash()
+		.map_err(|err| anyhow!("Unable to get latest blockhash: {}", err))?;
+	transaction.message.recent_blockhash = blockhash;
+
+	let fee = ensure_fee_within(client, &transaction.message, options.max_fee)?;
+
+	transaction
+		.try_sign(signers, blockhash)
+		.map_err(|err| anyhow!("Unable to sign transaction: {}", err))?;
+
+	let (signature, _) = send_with_retries(
+		client,
+		&mut transaction,
+		signers,
+		options.max_retries,
+		options.max_sign_attempts,
+		options.skip_preflight,
+		true,
+	)?;
+
+	Ok(Some((signature, fee)))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn row(tokenizer: &Pubkey, amount: &str, underlying_mint: &Pubkey) -> String {
+		format!("{},{},{}", tokenizer, amount, underlying_mint)
+	}
+
+	#[test]
+	fn rows_keep_their_line_numbers() {
+		let (tokenizer, underlying_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+		let contents = format!(
+			"{}\n{}\n\n {} \n",
+			HEADER,
+			row(&tokenizer, "10", &underlying_mint),
+			row(&tokenizer, "20", &underlying_mint)
+		);
+
+		let rows = parse_batch("batch.csv", &contents).unwrap();
+		let parsed: Vec<(usize, Pubkey, u64, Pubkey)> = rows
+			.iter()
+			.map(|row| (row.line, row.tokenizer, row.amount, row.underlying_mint))
+			.collect();
+		assert_eq!(
+			parsed,
+			[
+				(2, tokenizer, 10, underlying_mint),
+				(4, tokenizer, 20, underlying_mint)
+			]
+		);
+	}
+
+	#[test]
+	fn batch_requires_the_header() {
+		let (tokenizer, underlying_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+		assert!(parse_batch("batch.csv", "").is_err());
+		assert!(parse_batch("batch.csv", &row(&tokenizer, "10", &underlying_mint)).is_err());
+		assert!(parse_batch("batch.csv", "underlying_mint,amount,tokenizer\n").is_err());
+		assert!(parse_batch("batch.csv", &format!(" {} \n", HEADER))
+			.unwrap()
+			.is_empty());
+	}
+
+	#[test]
+	fn bad_rows_report_their_line() {
+		let (tokenizer, underlying_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+		let error = |contents: &str| {
+			parse_batch("batch.csv", &format!("{}\n\n{}\n", HEADER, contents))
+				.err()
+				.expect("row is rejected")
+				.to_string()
+		};
+
+		assert!(error(&format!("{},10", tokenizer)).starts_with("Line 3: expected 3 columns"));
+		assert!(error(&row(&tokenizer, "0", &underlying_mint))
+			.starts_with("Line 3: amount must be greater than zero"));
+		assert!(
+			error(&row(&tokenizer, "-1", &underlying_mint)).starts_with("Line 3: invalid amount")
+		);
+		assert!(error(&format!("not-a-pubkey,10,{}", underlying_mint))
+			.starts_with("Line 3: invalid tokenizer"));
+		assert!(error(&format!("{},10,not-a-pubkey", tokenizer))
+			.starts_with("Line 3: invalid underlying mint"));
+	}
+
+	fn checkpoint_entry(row: &BatchRow, signature: &Signature) -> String {
+		format!("{},{},{}\n", row.line, row.columns(), signature)
+	}
+
+	fn batch_rows() -> Vec<BatchRow> {
+		let (tokenizer, underlying_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+		let contents = format!(
+			"{}\n{}\n{}\n",
+			HEADER,
+			row(&tokenizer, "10", &underlying_mint),
+			row(&tokenizer, "20", &underlying_mint)
+		);
+		parse_batch("batch.csv", &contents).unwrap()
+	}
+
+	#[test]
+	fn checkpoint_maps_lines_to_signatures() {
+		let rows = batch_rows();
+		let signature = Signature::from([1; 64]);
+		let contents = format!(
+			"{}\n{}",
+			CHECKPOINT_HEADER,
+			checkpoint_entry(&rows[1], &signature)
+		);
+
+		let recorded = parse_checkpoint("checkpoint.csv", &contents, &rows).unwrap();
+		assert_eq!(recorded, BTreeMap::from([(3, signature)]));
+		assert!(parse_checkpoint("checkpoint.csv", "", &rows)
+			.unwrap()
+			.is_empty());
+	}
+
+	#[test]
+	fn checkpoint_must_match_the_batch_file() {
+		let rows = batch_rows();
+		let signature = Signature::from([1; 64]);
+		let parse = |entry: String| {
+			parse_checkpoint(
+				"checkpoint.csv",
+				&format!("{}\n{}", CHECKPOINT_HEADER, entry),
+				&rows,
+			)
+		};
+
+		// The row at line 2 recorded with the amount of line 3
+		let edited = format!("2,{},{}\n", rows[1].columns(), signature);
+		assert!(parse(edited).is_err());
+
+		let missing = format!("9,{},{}\n", rows[0].columns(), signature);
+		assert!(parse(missing).is_err());
+
+		let unsigned = format!("2,{},not-a-signature\n", rows[0].columns());
+		assert!(parse(unsigned).is_err());
+
+		assert!(parse_checkpoint("checkpoint.csv", "line,signature\n", &rows).is_err());
+	}
+
+	#[test]
+	fn appended_checkpoint_reads_back() {
+		let rows = batch_rows();
+		let path =
+			std::env::temp_dir().join(format!("sclr-checkpoint-{}.csv", Pubkey::new_unique()));
+		let path = path.to_str().expect("temp dir is valid UTF-8");
+		let (first, second) = (Signature::from([1; 64]), Signature::from([2; 64]));
+
+		append_checkpoint(path, &[&rows[0]], &first).unwrap();
+		append_checkpoint(path, &[&rows[1]], &second).unwrap();
+		let recorded = read_checkpoint(path, &rows);
+		fs::remove_file(path).unwrap();
+
+		assert_eq!(recorded.unwrap(), BTreeMap::from([(2, first), (3, second)]));
+		assert!(read_checkpoint(path, &rows).unwrap().is_empty());
+	}
+}
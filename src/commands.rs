@@ -0,0 +1,528 @@
+use {
+	crate::{
+		error::CliError,
+		pda::{get_principal_mint_address, get_tokenizer_address, get_yield_mint_address},
+		InstructionCommonFields, TerminateCommonFields,
+	},
+	sclr_token::{instruction, Expiry},
+	solana_program::{instruction::Instruction, pubkey::Pubkey},
+	spl_associated_token_account::{
+		get_associated_token_address, get_associated_token_address_with_program_id,
+	},
+};
+
+/// Everything instruction building needs beyond the command's fields, resolved by `main` so the
+/// builders below never touch an RPC.
+pub struct Context {
+	/// Program the built instructions are addressed to
+	pub program_id: Pubkey,
+	/// Signer authorising the instruction, the Squads vault when proposing through a multisig
+	pub authority: Pubkey,
+	/// Token program owning the underlying mint
+	pub underlying_token_program: Pubkey,
+}
+
+/// Accounts created when initializing the tokenizer for an underlying mint and expiry date.
+pub struct InitAccounts {
+	pub tokenizer: Pubkey,
+	pub underlying_vault: Pubkey,
+	pub principal_mint: Pubkey,
+	pub yield_mint: Pubkey,
+}
+
+impl Context {
+	pub fn init_accounts(&self, underlying_mint: &Pubkey, expiry_date: i64) -> InitAccounts {
+		let (tokenizer, _) = get_tokenizer_address(underlying_mint, expiry_date, &self.program_id);
+
+		InitAccounts {
+			tokenizer,
+			underlying_vault: self.underlying_vault(&tokenizer, underlying_mint),
+			principal_mint: get_principal_mint_address(&tokenizer, &self.program_id).0,
+			yield_mint: get_yield_mint_address(&tokenizer, &self.program_id).0,
+		}
+	}
+
+	fn underlying_vault(&self, tokenizer: &Pubkey, underlying_mint: &Pubkey) -> Pubkey {
+		get_associated_token_address_with_program_id(
+			tokenizer,
+			underlying_mint,
+			&self.underlying_token_program,
+		)
+	}
+
+	fn user_underlying_account(&self, underlying_mint: &Pubkey) -> Pubkey {
+		get_associated_token_address_with_program_id(
+			&self.authority,
+			underlying_mint,
+			&self.underlying_token_program,
+		)
+	}
+
+	fn finish(&self, mut instruction: Instruction) -> Instruction {
+		instruction.program_id = self.program_id;
+		instruction
+	}
+}
+
+pub fn build_init_tokenizer(
+	ctx: &Context,
+	underlying_mint: &Pubkey,
+	expiry: Expiry,
+	expiry_date: i64,
+) -> Result<Instruction, CliError> {
+	let accounts = ctx.init_accounts(underlying_mint, expiry_date);
+
+	//TODO: Calculation methodology for the fixed APY of the principal token
+	//NOTE: placeholder
+	let fixed_apy = 0;
+
+	instruction::init_tokenizer(
+		&accounts.tokenizer,
+		&ctx.authority,
+		&accounts.underlying_vault,
+		underlying_mint,
+		&accounts.principal_mint,
+		&accounts.yield_mint,
+		expiry,
+		fixed_apy,
+	)
+	.map(|instruction| ctx.finish(instruction))
+	.map_err(|err| CliError::InstructionBuild("InitializeTokenizer", err))
+}
+
+pub fn build_init_mints(
+	ctx: &Context,
+	underlying_mint: &Pubkey,
+	expiry: Expiry,
+	expiry_date: i64,
+) -> Result<Instruction, CliError> {
+	let accounts = ctx.init_accounts(underlying_mint, expiry_date);
+
+	instruction::init_mints(
+		&accounts.tokenizer,
+		&ctx.authority,
+		underlying_mint,
+		&accounts.principal_mint,
+		&accounts.yield_mint,
+		expiry,
+	)
+	.map(|instruction| ctx.finish(instruction))
+	.map_err(|err| CliError::InstructionBuild("InitializeMints", err))
+}
+
+pub fn build_init_tokenizer_and_mints(
+	ctx: &Context,
+	underlying_mint: &Pubkey,
+	expiry: Expiry,
+	expiry_date: i64,
+) -> Result<Instruction, CliError> {
+	let accounts = ctx.init_accounts(underlying_mint, expiry_date);
+
+	//TODO: Calculation methodology for the fixed APY of the principal token
+	//NOTE: placeholder
+	let fixed_apy = 0;
+
+	instruction::init_tokenizer_and_mints(
+		&accounts.tokenizer,
+		&ctx.authority,
+		&accounts.underlying_vault,
+		underlying_mint,
+		&accounts.principal_mint,
+		&accounts.yield_mint,
+		expiry,
+		fixed_apy,
+	)
+	.map(|instruction| ctx.finish(instruction))
+	.map_err(|err| CliError::InstructionBuild("InitializeTokenizerAndMints", err))
+}
+
+/// Deposits `amount` of the underlying into the tokenizer vault without tokenizing it.
+pub fn build_deposit(
+	ctx: &Context,
+	fields: &InstructionCommonFields,
+	amount: u64,
+) -> Result<Instruction, CliError> {
+	let tokenizer = &fields.lysergic_tokenizer_address;
+	let underlying_mint = fields.underlying_mint_address();
+
+	instruction::deposit_underlying(
+		tokenizer,
+		&ctx.authority,
+		&ctx.underlying_vault(tokenizer, &underlying_mint),
+		&underlying_mint,
+		amount,
+	)
+	.map(|instruction| ctx.finish(instruction))
+	.map_err(|err| CliError::InstructionBuild("Deposit", err))
+}
+
+pub fn build_tokenize_principal(
+	ctx: &Context,
+	fields: &InstructionCommonFields,
+) -> Result<Instruction, CliError> {
+	let tokenizer = &fields.lysergic_tokenizer_address;
+	let (principal_mint_address, _) = get_principal_mint_address(tokenizer, &ctx.program_id);
+
+	instruction::tokenize_principal(
+		tokenizer,
+		&principal_mint_address,
+		&ctx.authority,
+		&get_associated_token_address(&ctx.authority, &principal_mint_address),
+		fields.amount(),
+	)
+	.map(|instruction| ctx.finish(instruction))
+	.map_err(|err| CliError::InstructionBuild("TokenizePrincipal", err))
+}
+
+pub fn build_tokenize_yield(
+	ctx: &Context,
+	fields: &InstructionCommonFields,
+) -> Result<Instruction, CliError> {
+	let tokenizer = &fields.lysergic_tokenizer_address;
+	let (yield_mint_address, _) = get_yield_mint_address(tokenizer, &ctx.program_id);
+
+	instruction::tokenize_yield(
+		tokenizer,
+		&yield_mint_address,
+		&ctx.authority,
+		&get_associated_token_address(&ctx.authority, &yield_mint_address),
+		fields.amount(),
+	)
+	.map(|instruction| ctx.finish(instruction))
+	.map_err(|err| CliError::InstructionBuild("TokenizeYield", err))
+}
+
+/// Deposits and tokenizes `amount` of the underlying, which may be less than the fields' amount
+/// when only a fraction is tokenized.
+pub fn build_deposit_and_tokenize(
+	ctx: &Context,
+	fields: &InstructionCommonFields,
+	amount: u64,
+) -> Result<Instruction, CliError> {
+	let tokenizer = &fields.lysergic_tokenizer_address;
+	let underlying_mint = fields.underlying_mint_address();
+	let (principal_mint_address, _) = get_principal_mint_address(tokenizer, &ctx.program_id);
+	let (yield_mint_address, _) = get_yield_mint_address(tokenizer, &ctx.program_id);
+
+	instruction::deposit_and_tokenize(
+		tokenizer,
+		&ctx.underlying_vault(tokenizer, &underlying_mint),
+		&principal_mint_address,
+		&yield_mint_address,
+		&ctx.authority,
+		&ctx.user_underlying_account(&underlying_mint),
+		&get_associated_token_address(&ctx.authority, &principal_mint_address),
+		&get_associated_token_address(&ctx.authority, &yield_mint_address),
+		amount,
+	)
+	.map(|instruction| ctx.finish(instruction))
+	.map_err(|err| CliError::InstructionBuild("DepositAndTokenize", err))
+}
+
+pub fn build_redeem_principal(
+	ctx: &Context,
+	fields: &InstructionCommonFields,
+) -> Result<Instruction, CliError> {
+	let tokenizer = &fields.lysergic_tokenizer_address;
+	let underlying_mint = fields.underlying_mint_address();
+	let (principal_mint_address, _) = get_principal_mint_address(tokenizer, &ctx.program_id);
+
+	instruction::redeem_mature_principal(
+		tokenizer,
+		&ctx.underlying_vault(tokenizer, &underlying_mint),
+		&underlying_mint,
+		&principal_mint_address,
+		&ctx.authority,
+		&ctx.user_underlying_account(&underlying_mint),
+		&get_associated_token_address(&ctx.authority, &principal_mint_address),
+		fields.amount(),
+	)
+	.map(|instruction| ctx.finish(instruction))
+	.map_err(|err| CliError::InstructionBuild("RedeemPrincipalOnly", err))
+}
+
+pub fn build_claim_yield(
+	ctx: &Context,
+	fields: &InstructionCommonFields,
+) -> Result<Instruction, CliError> {
+	let tokenizer = &fields.lysergic_tokenizer_address;
+	let underlying_mint = fields.underlying_mint_address();
+	let (yield_mint_address, _) = get_yield_mint_address(tokenizer, &ctx.program_id);
+
+	instruction::claim_yield(
+		tokenizer,
+		&underlying_mint,
+		&yield_mint_address,
+		&ctx.authority,
+		&ctx.user_underlying_account(&underlying_mint),
+		&get_associated_token_address(&ctx.authority, &yield_mint_address),
+		fields.amount(),
+	)
+	.map(|instruction| ctx.finish(instruction))
+	.map_err(|err| CliError::InstructionBuild("ClaimYield", err))
+}
+
+pub fn build_redeem_principal_and_yield(
+	ctx: &Context,
+	fields: &InstructionCommonFields,
+) -> Result<Instruction, CliError> {
+	let tokenizer = &fields.lysergic_tokenizer_address;
+	let underlying_mint = fields.underlying_mint_address();
+	let (principal_mint_address, _) = get_principal_mint_address(tokenizer, &ctx.program_id);
+	let (yield_mint_address, _) = get_yield_mint_address(tokenizer, &ctx.program_id);
+
+	instruction::redeem_principal_and_yield(
+		tokenizer,
+		&ctx.underlying_vault(tokenizer, &underlying_mint),
+		&underlying_mint,
+		&principal_mint_address,
+		&yield_mint_address,
+		&ctx.authority,
+		&ctx.user_underlying_account(&underlying_mint),
+		&get_associated_token_address(&ctx.authority, &principal_mint_address),
+		&get_associated_token_address(&ctx.authority, &yield_mint_address),
+		fields.amount(),
+	)
+	.map(|instruction| ctx.finish(instruction))
+	.map_err(|err| CliError::InstructionBuild("RedeemPrincipalAndYield", err))
+}
+
+pub fn build_terminate(
+	ctx: &Context,
+	fields: &TerminateCommonFields,
+) -> Result<Instruction, CliError> {
+	let tokenizer = &fields.lysergic_tokenizer_address;
+	let (principal_mint_address, _) = get_principal_mint_address(tokenizer, &ctx.program_id);
+	let (yield_mint_address, _) = get_yield_mint_address(tokenizer, &ctx.program_id);
+
+	instruction::terminate(
+		tokenizer,
+		&ctx.authority,
+		&ctx.underlying_vault(tokenizer, &fields.underlying_mint_address),
+		&get_associated_token_address(tokenizer, &principal_mint_address),
+		&get_associated_token_address(tokenizer, &yield_mint_address),
+	)
+	.map(|instruction| ctx.finish(instruction))
+	.map_err(|err| CliError::InstructionBuild("Terminate", err))
+}
+
+pub fn build_terminate_tokenizer(
+	ctx: &Context,
+	fields: &TerminateCommonFields,
+) -> Result<Instruction, CliError> {
+	let tokenizer = &fields.lysergic_tokenizer_address;
+
+	instruction::terminate_tokenizer(
+		tokenizer,
+		&ctx.authority,
+		&ctx.underlying_vault(tokenizer, &fields.underlying_mint_address),
+	)
+	.map(|instruction| ctx.finish(instruction))
+	.map_err(|err| CliError::InstructionBuild("TerminateTokenizer", err))
+}
+
+pub fn build_terminate_mints(
+	ctx: &Context,
+	fields: &TerminateCommonFields,
+) -> Result<Instruction, CliError> {
+	let tokenizer = &fields.lysergic_tokenizer_address;
+	let (principal_mint_address, _) = get_principal_mint_address(tokenizer, &ctx.program_id);
+	let (yield_mint_address, _) = get_yield_mint_address(tokenizer, &ctx.program_id);
+
+	instruction::terminate_mints(
+		tokenizer,
+		&ctx.authority,
+		&principal_mint_address,
+		&yield_mint_address,
+	)
+	.map(|instruction| ctx.finish(instruction))
+	.map_err(|err| CliError::InstructionBuild("TerminateMints", err))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn context() -> Context {
+		Context {
+			program_id: Pubkey::new_unique(),
+			authority: Pubkey::new_unique(),
+			underlying_token_program: spl_token_2022::id(),
+		}
+	}
+
+	#[test]
+	fn init_accounts_follow_the_context() {
+		let ctx = context();
+		let underlying_mint = Pubkey::new_unique();
+		let expiry_date = 1_767_139_200;
+
+		let accounts = ctx.init_accounts(&underlying_mint, expiry_date);
+		let (tokenizer, _) = get_tokenizer_address(&underlying_mint, expiry_date, &ctx.program_id);
+
+		assert_eq!(accounts.tokenizer, tokenizer);
+		assert_eq!(
+			accounts.underlying_vault,
+			get_associated_token_address_with_program_id(
+				&tokenizer,
+				&underlying_mint,
+				&spl_token_2022::id()
+			)
+		);
+		assert_eq!(
+			accounts.principal_mint,
+			get_principal_mint_address(&tokenizer, &ctx.program_id).0
+		);
+		assert_eq!(
+			accounts.yield_mint,
+			get_yield_mint_address(&tokenizer, &ctx.program_id).0
+		);
+	}
+
+	fn keys(instruction: &Instruction) -> Vec<Pubkey> {
+		instruction
+			.accounts
+			.iter()
+			.map(|account| account.pubkey)
+			.collect()
+	}
+
+	/// Reads the amount the instruction data ends with.
+	fn amount(instruction: &Instruction) -> u64 {
+		crate::decode::decode_instruction(&instruction.data).expect("data decodes");
+
+		let amount = instruction.data[instruction.data.len() - 8..]
+			.try_into()
+			.expect("data ends with the amount");
+		u64::from_le_bytes(amount)
+	}
+
+	fn is_signer(instruction: &Instruction, pubkey: &Pubkey) -> bool {
+		instruction
+			.accounts
+			.iter()
+			.any(|account| account.pubkey == *pubkey && account.is_signer)
+	}
+
+	#[test]
+	fn deposit_and_tokenize_uses_the_given_amount() {
+		let ctx = context();
+		let fields =
+			InstructionCommonFields::new(Pubkey::new_unique(), Pubkey::new_unique(), 1_000);
+
+		let instruction = build_deposit_and_tokenize(&ctx, &fields, 400).unwrap();
+
+		assert_eq!(instruction.program_id, ctx.program_id);
+		assert!(is_signer(&instruction, &ctx.authority));
+		assert_eq!(amount(&instruction), 400);
+	}
+
+	#[test]
+	fn underlying_accounts_follow_the_context_token_program() {
+		let ctx = context();
+		let fields = InstructionCommonFields::new(Pubkey::new_unique(), Pubkey::new_unique(), 250);
+		let tokenizer = fields.lysergic_tokenizer_address;
+		let underlying_mint = fields.underlying_mint_address();
+		let (principal_mint, _) = get_principal_mint_address(&tokenizer, &ctx.program_id);
+		let (yield_mint, _) = get_yield_mint_address(&tokenizer, &ctx.program_id);
+
+		let instruction = build_redeem_principal_and_yield(&ctx, &fields).unwrap();
+		let keys = keys(&instruction);
+
+		// The underlying lives under Token-2022 here, the principal and yield mints never do
+		for account in [
+			get_associated_token_address_with_program_id(
+				&tokenizer,
+				&underlying_mint,
+				&spl_token_2022::id(),
+			),
+			get_associated_token_address_with_program_id(
+				&ctx.authority,
+				&underlying_mint,
+				&spl_token_2022::id(),
+			),
+			get_associated_token_address_with_program_id(
+				&ctx.authority,
+				&principal_mint,
+				&spl_token::id(),
+			),
+			get_associated_token_address_with_program_id(
+				&ctx.authority,
+				&yield_mint,
+				&spl_token::id(),
+			),
+		] {
+			assert!(keys.contains(&account), "missing {}", account);
+		}
+		assert!(
+			!keys.contains(&get_associated_token_address_with_program_id(
+				&ctx.authority,
+				&underlying_mint,
+				&spl_token::id(),
+			))
+		);
+		assert!(is_signer(&instruction, &ctx.authority));
+		assert_eq!(amount(&instruction), 250);
+	}
+
+	#[test]
+	fn claim_yield_leaves_the_vault_and_principal_alone() {
+		let ctx = context();
+		let fields = InstructionCommonFields::new(Pubkey::new_unique(), Pubkey::new_unique(), 75);
+		let tokenizer = fields.lysergic_tokenizer_address;
+
+		let instruction = build_claim_yield(&ctx, &fields).unwrap();
+		let keys = keys(&instruction);
+
+		let (yield_mint, _) = get_yield_mint_address(&tokenizer, &ctx.program_id);
+		assert!(keys.contains(&yield_mint));
+		assert!(keys.contains(&get_associated_token_address(&ctx.authority, &yield_mint)));
+		assert!(!keys.contains(&get_principal_mint_address(&tokenizer, &ctx.program_id).0));
+		assert!(
+			!keys.contains(&ctx.underlying_vault(&tokenizer, &fields.underlying_mint_address()))
+		);
+		assert_eq!(amount(&instruction), 75);
+	}
+
+	#[test]
+	fn terminate_closes_the_tokenizer_token_accounts() {
+		let ctx = context();
+		let fields = TerminateCommonFields {
+			lysergic_tokenizer_address: Pubkey::new_unique(),
+			underlying_mint_address: Pubkey::new_unique(),
+		};
+		let tokenizer = fields.lysergic_tokenizer_address;
+		let (principal_mint, _) = get_principal_mint_address(&tokenizer, &ctx.program_id);
+		let (yield_mint, _) = get_yield_mint_address(&tokenizer, &ctx.program_id);
+
+		let instruction = build_terminate(&ctx, &fields).unwrap();
+		let keys = keys(&instruction);
+
+		assert_eq!(instruction.program_id, ctx.program_id);
+		assert!(keys.contains(&get_associated_token_address(&tokenizer, &principal_mint)));
+		assert!(keys.contains(&get_associated_token_address(&tokenizer, &yield_mint)));
+		assert!(!keys.contains(&get_associated_token_address(
+			&ctx.authority,
+			&principal_mint
+		)));
+		assert!(is_signer(&instruction, &ctx.authority));
+	}
+
+	#[test]
+	fn terminate_mints_leaves_the_vault_alone() {
+		let ctx = context();
+		let fields = TerminateCommonFields {
+			lysergic_tokenizer_address: Pubkey::new_unique(),
+			underlying_mint_address: Pubkey::new_unique(),
+		};
+		let tokenizer = fields.lysergic_tokenizer_address;
+
+		let instruction = build_terminate_mints(&ctx, &fields).unwrap();
+		let keys = keys(&instruction);
+
+		assert!(keys.contains(&get_principal_mint_address(&tokenizer, &ctx.program_id).0));
+		assert!(keys.contains(&get_yield_mint_address(&tokenizer, &ctx.program_id).0));
+		assert!(!keys.contains(&ctx.underlying_vault(&tokenizer, &fields.underlying_mint_address)));
+		assert!(is_signer(&instruction, &ctx.authority));
+	}
+}
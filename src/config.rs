@@ -0,0 +1,157 @@
+use {
+	anyhow::{anyhow, Result},
+	serde::{de, Deserialize, Deserializer},
+	solana_program::pubkey::Pubkey,
+	std::{collections::BTreeMap, env, fs, io, path::PathBuf, str::FromStr},
+};
+
+const CONFIG_DIR: &str = ".config/sclr";
+const MATURITY_CALENDAR_FILE: &str = "maturities.toml";
+const ENV_PROFILES_FILE: &str = "profiles.toml";
+const ALIASES_FILE: &str = "aliases.toml";
+
+/// Bundle of connection settings selected with `--env-profile`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnvProfile {
+	pub rpc_url: Option<String>,
+	pub commitment: Option<String>,
+	pub keypair_path: Option<String>,
+	/// Tokenizer program id used when `--program-id` is not given
+	#[serde(default, deserialize_with = "deserialize_pubkey")]
+	pub program_id: Option<Pubkey>,
+	/// Cap on the amount of a single tokenize or redeem
+	pub max_operation_amount: Option<u64>,
+	/// Per-token caps keyed by underlying mint address, taking precedence over the global cap
+	#[serde(default)]
+	pub max_operation_amounts: BTreeMap<String, u64>,
+}
+
+/// Reads an optional base58 pubkey, which is how addresses are written in the profiles file.
+fn deserialize_pubkey<'de, D: Deserializer<'de>>(
+	deserializer: D,
+) -> Result<Option<Pubkey>, D::Error> {
+	Option::<String>::deserialize(deserializer)?
+		.map(|value| Pubkey::from_str(&value).map_err(de::Error::custom))
+		.transpose()
+}
+
+impl EnvProfile {
+	/// Returns the operation amount cap that applies to `underlying_mint`, if any.
+	pub fn max_operation_amount(&self, underlying_mint: &Pubkey) -> Option<u64> {
+		self.max_operation_amounts
+			.get(&underlying_mint.to_string())
+			.copied()
+			.or(self.max_operation_amount)
+	}
+}
+
+/// Returns the path of `file_name` within the CLI's config directory.
+pub fn config_file_path(file_name: &str) -> Result<PathBuf> {
+	let home = env::var("HOME").map_err(|_| anyhow!("Unable to locate home directory"))?;
+	Ok(PathBuf::from(home).join(CONFIG_DIR).join(file_name))
+}
+
+/// Resolves a named maturity to its expiry value using the calendar at `calendar_path`, or the
+/// default calendar when no path is given. The calendar is a flat TOML table of names to
+/// expiry values.
+pub fn resolve_maturity(calendar_path: Option<&str>, name: &str) -> Result<i64> {
+	let path = match calendar_path {
+		Some(path) => PathBuf::from(path),
+		None => config_file_path(MATURITY_CALENDAR_FILE)?,
+	};
+
+	let contents = fs::read_to_string(&path).map_err(|err| {
+		anyhow!(
+			"Unable to read maturity calendar {}: {}",
+			path.display(),
+			err
+		)
+	})?;
+
+	let calendar: BTreeMap<String, i64> = toml::from_str(&contents).map_err(|err| {
+		anyhow!(
+			"Unable to parse maturity calendar {}: {}",
+			path.display(),
+			err
+		)
+	})?;
+
+	calendar.get(name).copied().ok_or_else(|| {
+		anyhow!(
+			"Unknown maturity `{}`, available maturities: {}",
+			name,
+			calendar.keys().cloned().collect::<Vec<_>>().join(", ")
+		)
+	})
+}
+
+/// Loads the environment profile `name` from the profiles file, a TOML document with one table
+/// per profile.
+pub fn load_env_profile(name: &str) -> Result<EnvProfile> {
+	let path = config_file_path(ENV_PROFILES_FILE)?;
+
+	let contents = fs::read_to_string(&path).map_err(|err| {
+		anyhow!(
+			"Unable to read environment profiles {}: {}",
+			path.display(),
+			err
+		)
+	})?;
+
+	let mut profiles: BTreeMap<String, EnvProfile> = toml::from_str(&contents).map_err(|err| {
+		anyhow!(
+			"Unable to parse environment profiles {}: {}",
+			path.display(),
+			err
+		)
+	})?;
+
+	let available_profiles = profiles.keys().cloned().collect::<Vec<_>>().join(", ");
+	profiles.remove(name).ok_or_else(|| {
+		anyhow!(
+			"Unknown environment profile `{}`, available profiles: {}",
+			name,
+			available_profiles
+		)
+	})
+}
+
+/// Loads the address aliases, a flat TOML table of names to base58 pubkeys. A missing aliases
+/// file, or no home directory to look for one in, has no aliases.
+pub fn load_aliases() -> Result<BTreeMap<String, String>> {
+	let Ok(path) = config_file_path(ALIASES_FILE) else {
+		return Ok(BTreeMap::new());
+	};
+
+	let contents = match fs::read_to_string(&path) {
+		Ok(contents) => contents,
+		Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+		Err(err) => {
+			return Err(anyhow!(
+				"Unable to read aliases {}: {}",
+				path.display(),
+				err
+			))
+		}
+	};
+
+	toml::from_str(&contents)
+		.map_err(|err| anyhow!("Unable to parse aliases {}: {}", path.display(), err))
+}
+
+/// Writes `aliases` to the aliases file, creating the config directory when needed.
+pub fn save_aliases(aliases: &BTreeMap<String, String>) -> Result<()> {
+	let path = config_file_path(ALIASES_FILE)?;
+
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)
+			.map_err(|err| anyhow!("Unable to create {}: {}", dir.display(), err))?;
+	}
+
+	let contents =
+		toml::to_string(aliases).map_err(|err| anyhow!("Unable to serialize aliases: {}", err))?;
+
+	fs::write(&path, contents)
+		.map_err(|err| anyhow!("Unable to write aliases {}: {}", path.display(), err))
+}
@@ -0,0 +1,191 @@
+use {
+	crate::output::Report,
+	anyhow::{anyhow, Result},
+	base64::{prelude::BASE64_STANDARD, Engine},
+	borsh::BorshDeserialize,
+	num_traits::FromPrimitive,
+	sclr_token::{error::TokenizerError, instruction::TokenizerInstruction},
+	serde::Serialize,
+	solana_program::{
+		instruction::{AccountMeta, Instruction, InstructionError},
+		message::Message,
+		pubkey::Pubkey,
+	},
+	solana_sdk::{
+		sanitize::Sanitize,
+		signature::Signature,
+		transaction::{Transaction, TransactionError},
+	},
+};
+
+/// Decodes raw instruction data into the tokenizer program's instruction enum.
+pub fn decode_instruction(data: &[u8]) -> Result<TokenizerInstruction> {
+	TokenizerInstruction::try_from_slice(data)
+		.map_err(|err| anyhow!("Unable to decode tokenizer instruction data: {}", err))
+}
+
+/// Returns the account key at `index` of `message`, failing on an index past the end of the
+/// account keys instead of panicking.
+fn account_key(message: &Message, index: u8) -> Result<Pubkey> {
+	message
+		.account_keys
+		.get(index as usize)
+		.copied()
+		.ok_or_else(|| {
+			anyhow!(
+				"Account index {} is out of range of the message's {} account keys",
+				index,
+				message.account_keys.len()
+			)
+		})
+}
+
+/// Rebuilds the instructions of a compiled legacy message, restoring the signer and writable
+/// flags of each account from the message header.
+pub fn decompile_message(message: &Message) -> Result<Vec<Instruction>> {
+	message
+		.instructions
+		.iter()
+		.map(|compiled| {
+			Ok(Instruction {
+				program_id: account_key(message, compiled.program_id_index)?,
+				accounts: compiled
+					.accounts
+					.iter()
+					.map(|&index| {
+						Ok(AccountMeta {
+							pubkey: account_key(message, index)?,
+							is_signer: message.is_signer(index as usize),
+							is_writable: message.is_maybe_writable(index as usize, None),
+						})
+					})
+					.collect::<Result<_>>()?,
+				data: compiled.data.clone(),
+			})
+		})
+		.collect()
+}
+
+/// Describes a failed transaction with the message of the tokenizer program error behind a
+/// custom error code raised by a tokenizer instruction, followed by the code itself. Other
+/// errors and unknown codes are reported as-is.
+pub fn describe_transaction_error(err: &TransactionError, message: &Message) -> String {
+	let TransactionError::InstructionError(index, InstructionError::Custom(code)) = err else {
+		return err.to_string();
+	};
+
+	let program_id = message
+		.instructions
+		.get(*index as usize)
+		.and_then(|instruction| account_key(message, instruction.program_id_index).ok());
+
+	match TokenizerError::from_u32(*code) {
+		Some(program_error) if program_id == Some(crate::program_id()) => format!(
+			"Instruction {} failed: {} (custom program error {:#x})",
+			index, program_error, code
+		),
+		_ => err.to_string(),
+	}
+}
+
+/// Deserializes a base64-encoded legacy transaction, rejecting one whose message is malformed,
+/// such as instructions referring to accounts the message does not list.
+pub fn deserialize_transaction(transaction_base64: &str) -> Result<Transaction> {
+	let serialized_transaction = BASE64_STANDARD
+		.decode(transaction_base64.trim())
+		.map_err(|err| anyhow!("Unable to decode base64 transaction: {}", err))?;
+
+	let transaction: Transaction = bincode::deserialize(&serialized_transaction)
+		.map_err(|err| anyhow!("Unable to deserialize transaction: {}", err))?;
+
+	transaction
+		.sanitize()
+		.map_err(|err| anyhow!("Malformed transaction: {}", err))?;
+
+	Ok(transaction)
+}
+
+#[derive(Serialize)]
+struct DecodedAccount {
+	pubkey: String,
+	is_signer: bool,
+	is_writable: bool,
+}
+
+#[derive(Serialize)]
+struct DecodedInstruction {
+	program_id: String,
+	/// The decoded data of a tokenizer instruction, or why it could not be decoded
+	decoded: Option<String>,
+	accounts: Vec<DecodedAccount>,
+}
+
+/// Reports the fee payer, blockhash, signatures and instructions of `transaction`, decoding the
+/// data of tokenizer instructions.
+pub fn print_transaction(transaction: &Transaction, report: &mut Report) -> Result<()> {
+	let message = &transaction.message;
+
+	if let Some(fee_payer) = message.account_keys.first() {
+		report.field("Fee Payer", fee_payer);
+	}
+	report.field("Recent Blockhash", message.recent_blockhash);
+
+	report.list(
+		"Signatures",
+		message
+			.signer_keys()
+			.iter()
+			.zip(&transaction.signatures)
+			.map(|(signer, signature)| {
+				if *signature == Signature::default() {
+					format!("{}: missing", signer)
+				} else {
+					format!("{}: {}", signer, signature)
+				}
+			}),
+	);
+
+	let instructions: Vec<DecodedInstruction> = decompile_message(message)?
+		.into_iter()
+		.map(|instruction| DecodedInstruction {
+			program_id: instruction.program_id.to_string(),
+			decoded: (instruction.program_id == crate::program_id()).then(|| {
+				match decode_instruction(&instruction.data) {
+					Ok(decoded) => format!("{:?}", decoded),
+					Err(err) => err.to_string(),
+				}
+			}),
+			accounts: instruction
+				.accounts
+				.iter()
+				.map(|account| DecodedAccount {
+					pubkey: account.pubkey.to_string(),
+					is_signer: account.is_signer,
+					is_writable: account.is_writable,
+				})
+				.collect(),
+		})
+		.collect();
+
+	report.json_field("Instructions", &instructions)?;
+	if report.is_json() {
+		return Ok(());
+	}
+
+	for (index, instruction) in instructions.iter().enumerate() {
+		println!("Instruction {}: {}", index, instruction.program_id);
+
+		if let Some(ref decoded) = instruction.decoded {
+			println!("  Decoded: {}", decoded);
+		}
+
+		for (account_index, account) in instruction.accounts.iter().enumerate() {
+			println!(
+				"  {}: {} signer: {} writable: {}",
+				account_index, account.pubkey, account.is_signer, account.is_writable
+			);
+		}
+	}
+
+	Ok(())
+}
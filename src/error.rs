@@ -0,0 +1,50 @@
+use {
+	solana_client::client_error::ClientError,
+	solana_program::{program_error::ProgramError, pubkey::Pubkey},
+	solana_sdk::signature::Signature,
+	thiserror::Error,
+};
+
+/// Failures internal functions report, `main` wraps them in `anyhow` and the json output mode
+/// reports their code alongside the message.
+#[derive(Debug, Error)]
+pub enum CliError {
+	#[error("Underlying mint of tokenizer {0} could not be determined")]
+	MissingUnderlyingMint(Pubkey),
+	#[error("RPC request failed: {0}")]
+	RpcError(#[from] ClientError),
+	#[error("Unable to create `{0}` instruction: {1}")]
+	InstructionBuild(&'static str, ProgramError),
+	#[error("Account {0} not found")]
+	AccountNotFound(Pubkey),
+	#[error("Account {0} is not owned by the tokenizer program")]
+	WrongOwner(Pubkey),
+	#[error("Unable to deserialize tokenizer {0}: {1}")]
+	InvalidTokenizer(Pubkey, std::io::Error),
+	#[error("Transaction failed: {0}")]
+	TransactionFailed(String),
+	#[error("Unable to send transaction: {0}")]
+	SendFailed(ClientError),
+	#[error("Unable to confirm transaction {0}: {1}")]
+	ConfirmFailed(Signature, String),
+	#[error("Unable to send transaction to any endpoint:\n{0}")]
+	BroadcastFailed(String),
+}
+
+impl CliError {
+	/// A stable identifier for the failure, emitted by the json output mode.
+	pub fn code(&self) -> &'static str {
+		match self {
+			CliError::MissingUnderlyingMint(_) => "missing_underlying_mint",
+			CliError::RpcError(_) => "rpc_error",
+			CliError::InstructionBuild(..) => "instruction_build",
+			CliError::AccountNotFound(_) => "account_not_found",
+			CliError::WrongOwner(_) => "wrong_owner",
+			CliError::InvalidTokenizer(..) => "invalid_tokenizer",
+			CliError::TransactionFailed(_) => "transaction_failed",
+			CliError::SendFailed(_) => "send_failed",
+			CliError::ConfirmFailed(..) => "confirm_failed",
+			CliError::BroadcastFailed(_) => "broadcast_failed",
+		}
+	}
+}
@@ -0,0 +1,163 @@
+use {
+	crate::is_transient,
+	async_trait::async_trait,
+	log::debug,
+	serde_json::Value,
+	solana_client::{client_error::Result as ClientResult, rpc_request::RpcRequest},
+	solana_rpc_client::{
+		http_sender::HttpSender,
+		rpc_sender::{RpcSender, RpcTransportStats},
+	},
+	std::sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Sends each request to the endpoint that last served one, moving on to the next endpoint when
+/// it fails in transport. Errors returned by a node, such as a failed simulation, are passed
+/// through since every endpoint would return the same.
+pub struct FailoverSender<S = HttpSender> {
+	senders: Vec<S>,
+	current: AtomicUsize,
+}
+
+impl<S: RpcSender> FailoverSender<S> {
+	pub fn new(senders: Vec<S>) -> Self {
+		assert!(!senders.is_empty(), "failover needs at least one endpoint");
+
+		Self {
+			senders,
+			current: AtomicUsize::new(0),
+		}
+	}
+}
+
+#[async_trait]
+impl<S: RpcSender + Send + Sync> RpcSender for FailoverSender<S> {
+	async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+		let start = self.current.load(Ordering::Relaxed);
+		let mut last_err = None;
+
+		for offset in 0..self.senders.len() {
+			let index = (start + offset) % self.senders.len();
+			let sender = &self.senders[index];
+
+			match sender.send(request, params.clone()).await {
+				Err(err) if is_transient(&err) => {
+					debug!("RPC endpoint {} failed: {}", sender.url(), err);
+					last_err = Some(err);
+				}
+				result => {
+					if index != start {
+						debug!(
+							"RPC endpoint {} served {} after failover",
+							sender.url(),
+							request
+						);
+						self.current.store(index, Ordering::Relaxed);
+					}
+					return result;
+				}
+			}
+		}
+
+		Err(last_err.expect("every endpoint was tried"))
+	}
+
+	fn get_transport_stats(&self) -> RpcTransportStats {
+		self.senders
+			.iter()
+			.map(RpcSender::get_transport_stats)
+			.fold(RpcTransportStats::default(), |total, stats| {
+				RpcTransportStats {
+					request_count: total.request_count + stats.request_count,
+					elapsed_time: total.elapsed_time + stats.elapsed_time,
+					rate_limited_time: total.rate_limited_time + stats.rate_limited_time,
+				}
+			})
+	}
+
+	fn url(&self) -> String {
+		self.senders[self.current.load(Ordering::Relaxed)].url()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		crate::rpc::mock::MockSender,
+		serde_json::json,
+		solana_client::{
+			client_error::ClientError,
+			rpc_client::{RpcClient, RpcClientConfig},
+			rpc_request::{RpcError, RpcResponseErrorData},
+		},
+		solana_sdk::commitment_config::CommitmentConfig,
+		std::io,
+	};
+
+	fn slot() -> ClientResult<Value> {
+		Ok(json!(42))
+	}
+
+	fn connection_refused() -> ClientResult<Value> {
+		Err(ClientError::from(io::Error::new(
+			io::ErrorKind::ConnectionRefused,
+			"connection refused",
+		)))
+	}
+
+	fn simulation_failed() -> ClientResult<Value> {
+		Err(ClientError::from(RpcError::RpcResponseError {
+			code: -32002,
+			message: "Transaction simulation failed: custom program error: 0x1".to_string(),
+			data: RpcResponseErrorData::Empty,
+		}))
+	}
+
+	fn client(senders: Vec<MockSender>) -> RpcClient {
+		RpcClient::new_sender(
+			FailoverSender::new(senders),
+			RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+		)
+	}
+
+	#[test]
+	fn rotates_to_the_next_endpoint_on_transport_errors() {
+		let first = MockSender::new("http://first", connection_refused);
+		let second = MockSender::new("http://second", slot);
+		let (first_requests, second_requests) = (first.requests(), second.requests());
+		let client = client(vec![first, second]);
+
+		assert_eq!(client.get_slot().unwrap(), 42);
+		assert_eq!(client.url(), "http://second");
+
+		// The endpoint that served the last request keeps serving
+		assert_eq!(client.get_slot().unwrap(), 42);
+		assert_eq!(first_requests.load(Ordering::Relaxed), 1);
+		assert_eq!(second_requests.load(Ordering::Relaxed), 2);
+	}
+
+	#[test]
+	fn passes_program_errors_through_without_rotating() {
+		let first = MockSender::new("http://first", simulation_failed);
+		let second = MockSender::new("http://second", slot);
+		let second_requests = second.requests();
+		let client = client(vec![first, second]);
+
+		assert!(client.get_slot().is_err());
+		assert_eq!(client.url(), "http://first");
+		assert_eq!(second_requests.load(Ordering::Relaxed), 0);
+	}
+
+	#[test]
+	fn fails_once_every_endpoint_failed_in_transport() {
+		let first = MockSender::new("http://first", connection_refused);
+		let second = MockSender::new("http://second", connection_refused);
+		let (first_requests, second_requests) = (first.requests(), second.requests());
+		let client = client(vec![first, second]);
+
+		assert!(client.get_slot().is_err());
+		assert_eq!(first_requests.load(Ordering::Relaxed), 1);
+		assert_eq!(second_requests.load(Ordering::Relaxed), 1);
+	}
+}
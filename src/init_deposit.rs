@@ -0,0 +1,131 @@
+use {
+	crate::{
+		batch::fits_in_transaction,
+		commands::{self, InitAccounts},
+		create_missing_token_accounts, ensure_expiry_in_future, ensure_underlying_balance,
+		error::CliError,
+		output::Report,
+		rpc::RpcProvider,
+		InitDepositTokenizeFields, InstructionCommonFields,
+	},
+	anyhow::{anyhow, Result},
+	sclr_token::Expiry,
+	solana_program::{instruction::Instruction, pubkey::Pubkey},
+	spl_associated_token_account::get_associated_token_address_with_program_id,
+};
+
+/// Builds the instructions that initialize the tokenizer for an underlying mint and expiry
+/// unless it already exists, then deposit and tokenize `amount` into it, authorised by
+/// `ctx.authority`. The principal and yield token accounts are funded by `rent_payer`. The
+/// signer's underlying balance is only checked when `check_balance` is set.
+pub fn init_deposit_tokenize(
+	client: &impl RpcProvider,
+	ctx: &commands::Context,
+	rent_payer: &Pubkey,
+	fields: &InitDepositTokenizeFields,
+	now: i64,
+	allow_past_expiry: bool,
+	check_balance: bool,
+	report: &mut Report,
+) -> Result<Vec<Instruction>> {
+	if fields.amount == 0 {
+		return Err(anyhow!("Amount must be greater than zero"));
+	}
+
+	let underlying_mint_address = &fields.init_fields.underlying_mint_address;
+	let expiry = Expiry::from_i64(fields.init_fields.expiry()?)
+		.map_err(|err| anyhow!("Unable to parse the given value to `Expiry`: {}", err))?;
+	let expiry_date = expiry
+		.to_expiry_date(now)
+		.expect("Unable to convert expiry to expiry date");
+
+	if !allow_past_expiry {
+		ensure_expiry_in_future(expiry_date, now)?;
+	}
+
+	let InitAccounts {
+		tokenizer: tokenizer_address,
+		underlying_vault: underlying_vault_address,
+		principal_mint: principal_mint_address,
+		yield_mint: yield_mint_address,
+	} = ctx.init_accounts(underlying_mint_address, expiry_date);
+
+	report.field("Tokenizer Address", tokenizer_address);
+	report.field("Vault Address", underlying_vault_address);
+	report.field("Principal Mint Address", principal_mint_address);
+	report.field("Yield Mint Address", yield_mint_address);
+	report.field("Amount", fields.amount);
+
+	if check_balance {
+		let user_underlying_token_address = get_associated_token_address_with_program_id(
+			&ctx.authority,
+			underlying_mint_address,
+			&ctx.underlying_token_program,
+		);
+		ensure_underlying_balance(client, &user_underlying_token_address, fields.amount)?;
+	}
+
+	let tokenizer_exists = client
+		.get_account_with_commitment(&tokenizer_address, client.commitment())
+		.map_err(CliError::RpcError)?
+		.value
+		.is_some();
+
+	let mut instructions = Vec::new();
+	if tokenizer_exists {
+		eprintln!(
+			"Tokenizer {} already exists, skipping initialization",
+			tokenizer_address
+		);
+	} else {
+		instructions.push(commands::build_init_tokenizer_and_mints(
+			ctx,
+			underlying_mint_address,
+			expiry,
+			expiry_date,
+		)?);
+	}
+
+	// The principal and yield mints only exist once initialization lands, so their accounts are
+	// created alongside the deposit rather than checked against existing state
+	if tokenizer_exists {
+		instructions.extend(create_missing_token_accounts(
+			client,
+			rent_payer,
+			&ctx.authority,
+			&[
+				(principal_mint_address, spl_token::id()),
+				(yield_mint_address, spl_token::id()),
+			],
+		)?);
+	} else {
+		instructions.extend(
+			[principal_mint_address, yield_mint_address]
+				.iter()
+				.map(|mint| {
+					spl_associated_token_account::instruction::create_associated_token_account(
+						rent_payer,
+						&ctx.authority,
+						mint,
+						&spl_token::id(),
+					)
+				}),
+		);
+	}
+
+	instructions.push(commands::build_deposit_and_tokenize(
+		ctx,
+		&InstructionCommonFields::new(tokenizer_address, *underlying_mint_address, fields.amount),
+		fields.amount,
+	)?);
+
+	if !fits_in_transaction(&instructions, rent_payer) {
+		return Err(anyhow!(
+			"Initializing tokenizer {} and depositing do not fit in one transaction, run `init \
+			 tokenizer-mints` first",
+			tokenizer_address
+		));
+	}
+
+	Ok(instructions)
+}
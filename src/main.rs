@@ -1,28 +1,263 @@
 use {
+	crate::{
+		error::CliError,
+		pda::{get_principal_mint_address, get_tokenizer_address, get_yield_mint_address},
+		rpc::RpcProvider,
+	},
 	anyhow::{anyhow, Result},
-	clap::{command, Args, Parser, Subcommand},
-	sclr_token::{
-		get_principal_mint_address, get_tokenizer_address, get_yield_mint_address, instruction,
-		Expiry,
+	base64::{prelude::BASE64_STANDARD, Engine},
+	chrono::DateTime,
+	clap::{
+		command, error::ErrorKind, ArgMatches, Args, CommandFactory, FromArgMatches, Parser,
+		Subcommand, ValueEnum,
 	},
+	crossbeam_channel::RecvTimeoutError,
+	indicatif::{ProgressBar, ProgressFinish},
+	log::{debug, LevelFilter},
+	rand::Rng,
+	reqwest::header::{HeaderName, HeaderValue},
+	sclr_token::Expiry,
+	solana_account_decoder::UiAccountEncoding,
 	solana_cli_config,
-	solana_client::rpc_client::RpcClient,
-	solana_program::{instruction::Instruction, pubkey::Pubkey},
+	solana_client::{
+		client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+		pubsub_client::PubsubClient,
+		rpc_client::{RpcClient, RpcClientConfig},
+		rpc_config::{
+			RpcAccountInfoConfig, RpcSendTransactionConfig, RpcSignatureSubscribeConfig,
+			RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+		},
+		rpc_request::{RpcError, RpcResponseErrorData},
+		rpc_response::RpcSignatureResult,
+	},
+	solana_program::{
+		instruction::Instruction,
+		message::Message,
+		program_error::ProgramError,
+		program_pack::Pack,
+		pubkey::{ParsePubkeyError, Pubkey},
+	},
+	solana_remote_wallet::{
+		locator::Locator,
+		remote_keypair::{generate_remote_keypair, RemoteKeypair},
+		remote_wallet::maybe_wallet_manager,
+	},
+	solana_rpc_client::http_sender::HttpSender,
 	solana_sdk::{
+		clock::Slot,
 		commitment_config::CommitmentConfig,
-		signature::{read_keypair_file, Signer},
-		transaction::Transaction,
+		compute_budget::ComputeBudgetInstruction,
+		derivation_path::DerivationPath,
+		hash::Hash,
+		message::VersionedMessage,
+		signature::{read_keypair, read_keypair_file, Keypair, Signature, Signer},
+		signer::presigner::Presigner,
+		transaction::{Transaction, TransactionError},
+	},
+	solana_transaction_status::UiTransactionEncoding,
+	spl_token::state::Account as TokenAccount,
+	spl_token_2022::{extension::StateWithExtensions, state::Account as Token2022Account},
+	std::{
+		collections::BTreeMap,
+		io::{self, IsTerminal, Write},
+		str::FromStr,
+		sync::{mpsc, OnceLock},
+		thread,
+		time::{Duration, SystemTime, UNIX_EPOCH},
 	},
+	uuid::Uuid,
 };
 
+mod batch;
+mod commands;
+mod config;
+mod decode;
+mod error;
+mod failover;
+mod init_deposit;
+mod output;
+mod pda;
+mod positions;
+mod rpc;
+mod squads;
+mod state;
+
+/// Tokenizer program id, overridden by `--program-id`.
+static PROGRAM_ID: OnceLock<Pubkey> = OnceLock::new();
+
+/// Returns the id of the tokenizer program instructions are sent to and accounts are checked
+/// against.
+fn program_id() -> Pubkey {
+	*PROGRAM_ID.get_or_init(sclr_token::id)
+}
+
 #[derive(Parser, Debug)]
 struct Cli {
 	#[arg(short, long)]
 	config: Option<String>,
+	/// Tokenizer program id, for a copy of the program deployed at another address
+	#[arg(long, env = "LYST_PROGRAM_ID", value_parser = parse_pubkey)]
+	program_id: Option<Pubkey>,
+	/// RPC endpoint, repeat to fail over to the next endpoint on connection errors
 	#[arg(short, long)]
-	rpc: Option<String>,
-	#[arg(short, long)]
+	rpc: Vec<String>,
+	/// Payer keypair file, `usb://ledger` to sign on a Ledger, or `-` to read the keypair JSON
+	/// from stdin
+	#[arg(short, long, env = "LYST_PAYER")]
 	payer: Option<String>,
+	/// Keypair that pays the transaction fee, while the payer keypair still authorizes the
+	/// operation. `-` reads the keypair JSON from stdin
+	#[arg(long)]
+	fee_payer: Option<String>,
+	/// Memo attached to the transaction, signed by the fee payer
+	#[arg(long, value_parser = parse_memo)]
+	memo: Option<String>,
+	/// Answer yes to confirmation prompts, required when stdin is not a terminal
+	#[arg(short, long)]
+	yes: bool,
+	/// Log the RPC URL, fee payer, derived addresses and instruction accounts
+	#[arg(short, long)]
+	verbose: bool,
+	/// Commitment used for queries and confirmation, defaults to `confirmed`
+	#[arg(long, value_parser = ["processed", "confirmed", "finalized"])]
+	commitment: Option<String>,
+	/// Source of the current time used for expiry checks: `local`, `rpc` or `fixed:<timestamp>`
+	#[arg(long)]
+	cluster_time_source: Option<TimeSource>,
+	/// Print the signer and writable account keys of the transaction instead of sending it
+	#[arg(long)]
+	output_account_keys: bool,
+	/// Propose the transaction to this Squads multisig, executing it from the multisig's vault
+	#[arg(long, value_parser = parse_pubkey)]
+	squads_multisig: Option<Pubkey>,
+	/// After confirming the transaction, wait until it is finalized before reporting success
+	#[arg(long)]
+	confirm_with_commitment_escalation: bool,
+	/// Ignore the Solana CLI config file, requiring `--rpc` and `--payer` instead
+	#[arg(long)]
+	no_default_config: bool,
+	/// Report the net underlying received by a redeem or claim
+	#[arg(long)]
+	show_delta: bool,
+	/// Sign with the available keypair and print the signatures instead of sending
+	#[arg(long)]
+	sign_only: bool,
+	/// Recent blockhash to sign with instead of fetching the latest one
+	#[arg(long)]
+	blockhash: Option<Hash>,
+	/// Print the unsigned transaction as base64 for signing on an air-gapped machine, built
+	/// without fetching a blockhash or checking token accounts
+	#[arg(long, requires = "blockhash")]
+	offline: bool,
+	/// Signature collected from a `--sign-only` run, as `PUBKEY=SIGNATURE`
+	#[arg(long = "signer", value_parser = parse_presigner)]
+	signers: Vec<(Pubkey, Signature)>,
+	/// Print the raw data of each instruction as hex, decoding tokenizer instructions
+	#[arg(long)]
+	dump_ix_data: bool,
+	/// Print the accounts of the tokenizer instruction in the order the program expects them
+	#[arg(long)]
+	show_accounts: bool,
+	/// Websocket URL used to await confirmation via `signatureSubscribe` instead of polling
+	#[arg(long)]
+	ws_url: Option<String>,
+	/// Check referenced accounts against on-chain state before building instructions
+	#[arg(long)]
+	validate_accounts: bool,
+	/// Point at which the transaction is considered successful and the CLI stops waiting
+	#[arg(long, value_enum, default_value_t = SuccessOn::Confirmed)]
+	success_on: SuccessOn,
+	/// Print the signed transaction encoded as base64 instead of sending it
+	#[arg(long)]
+	output_tx_base64: bool,
+	/// Print the signed transaction encoded as base58 instead of sending it
+	#[arg(long)]
+	output_tx_base58: bool,
+	/// Print the fee, rent and simulated compute units of the transaction without sending it
+	#[arg(long)]
+	estimate_only: bool,
+	/// Print the pubkeys that must sign the transaction, without loading a keypair
+	#[arg(long)]
+	output_signers_needed: bool,
+	/// Compute budget as `limit=<units>,price=<micro-lamports>`, either key may be omitted
+	#[arg(long)]
+	compute_budget: Option<ComputeBudget>,
+	/// Compute unit limit requested for the transaction
+	#[arg(long, conflicts_with = "compute_budget")]
+	compute_unit_limit: Option<u32>,
+	/// Priority fee in micro-lamports per compute unit
+	#[arg(long, conflicts_with = "compute_budget")]
+	compute_unit_price: Option<u64>,
+	/// Proceed even when a safety check would abort the command
+	#[arg(long)]
+	force: bool,
+	/// Print the compiled transaction message before signing
+	#[arg(long)]
+	dump_compiled_message: bool,
+	/// Named environment profile from `~/.config/sclr/profiles.toml`
+	#[arg(long)]
+	env_profile: Option<String>,
+	/// Client id sent as an `x-client-id` header on RPC requests, generated when no value is given
+	#[arg(long)]
+	client_id: Option<Option<String>>,
+	/// Seconds to wait for each RPC request before failing, 30 when unset
+	#[arg(long)]
+	rpc_timeout: Option<u64>,
+	/// Print the inputs and bump of each derived address
+	#[arg(long)]
+	trace_derivations: bool,
+	/// Largest amount a single tokenize or redeem may process, overriding profile caps
+	#[arg(long)]
+	max_operation_amount: Option<u64>,
+	/// Additional RPC URLs to submit the transaction to in parallel, comma separated
+	#[arg(long, value_delimiter = ',')]
+	broadcast_to: Vec<String>,
+	/// Check that referenced token accounts belong to the same token program as their mint
+	#[arg(long)]
+	check_ata_owner_program: bool,
+	/// After sending, report the slot each writable account was updated at
+	#[arg(long)]
+	report_account_slots: bool,
+	/// Simulate instead of sending, against state no older than this slot
+	#[arg(long)]
+	simulate_at_slot: Option<Slot>,
+	/// Simulate the transaction and print its logs instead of sending it
+	#[arg(long)]
+	dry_run: bool,
+	/// Abort instead of sending when the transaction fee, priority fee included, exceeds this
+	/// many lamports
+	#[arg(long)]
+	max_fee: Option<u64>,
+	/// Send without preflight simulation. A transaction that would fail still lands and pays
+	/// its fee
+	#[arg(long)]
+	skip_preflight: bool,
+	/// Number of times a send that failed in transport is retried
+	#[arg(long, default_value_t = 3)]
+	max_retries: u32,
+	/// Number of times the transaction may be signed, re-signing with a fresh blockhash when
+	/// the previous one expired before it landed
+	#[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+	max_sign_attempts: u32,
+	/// Allow initializing a tokenizer whose expiry has already passed, for testing
+	#[arg(long)]
+	allow_past_expiry: bool,
+	/// Treat the underlying mint as a Token-2022 mint when its owner cannot be read
+	#[arg(long)]
+	token_2022: bool,
+	/// Fail instead of creating the signer's associated token accounts when they are missing
+	#[arg(long)]
+	no_create_ata: bool,
+	/// Output format, `json` prints a single object for scripts
+	#[arg(long, value_enum, default_value_t)]
+	output: output::OutputFormat,
+	/// Append a JSON record of the command, its addresses, amount and signature to this file
+	#[arg(long)]
+	output_file: Option<String>,
+	/// Fields to keep in the json output, comma separated, such as `signature,slot`. The
+	/// `--output-file` record keeps every field
+	#[arg(long, value_delimiter = ',')]
+	output_fields: Vec<String>,
 	#[command(subcommand)]
 	cmd: Commands,
 }
@@ -39,6 +274,136 @@ enum Commands {
 	Terminate(Terminate),
 	#[command(subcommand)]
 	Swap(Swap),
+	Replay(ReplayFields),
+	Positions(PositionsFields),
+	/// Print the tokenizer, vault and mint addresses for an underlying mint and expiry
+	Derive(InitializeCommonFields),
+	/// Initialize a tokenizer unless it exists, then deposit and tokenize into it
+	InitDepositTokenize(InitDepositTokenizeFields),
+	/// Print the state of a tokenizer
+	Status(StatusFields),
+	/// Poll a tokenizer's deposits and time to expiry until it expires
+	Watch(WatchFields),
+	/// List the tokenizers of the program with their underlying mint and expiry
+	ListMarkets(ListMarketsFields),
+	/// Print the signer's underlying, principal and yield balances for a tokenizer
+	Balances(BalancesFields),
+	/// Send a signed base64 transaction, such as one built with `--offline`
+	Submit(SubmitFields),
+	/// Print the fee payer, signatures and instructions of a base64 transaction
+	DecodeTx(DecodeTxFields),
+	/// Manage the names accepted in place of addresses
+	#[command(subcommand)]
+	Aliases(Aliases),
+	/// Shortcut for `tokenize deposit`
+	Deposit(DepositFields),
+	/// Shortcut for `redeem yield`
+	Claim(InstructionCommonFields),
+}
+
+impl Commands {
+	/// Expands top-level shortcuts into the nested commands they stand for.
+	fn expand_shortcuts(self) -> Self {
+		match self {
+			Commands::Deposit(deposit_fields) => {
+				Commands::Tokenize(Tokenize::Deposit(deposit_fields))
+			}
+			Commands::Claim(common_fields) => Commands::Redeem(Redeem::Yield(common_fields)),
+			command => command,
+		}
+	}
+
+	/// Returns the common fields of commands that move an amount of tokens.
+	fn instruction_fields(&self) -> Option<&InstructionCommonFields> {
+		match self {
+			Commands::Tokenize(tokenize) => match tokenize {
+				Tokenize::Deposit(DepositFields { common_fields, .. })
+				| Tokenize::PrincipalYield(DepositAndTokenizeFields { common_fields, .. })
+				| Tokenize::Principal(common_fields)
+				| Tokenize::Yield(common_fields) => Some(common_fields),
+				Tokenize::Batch(_) => None,
+			},
+			Commands::Redeem(redeem) => match redeem {
+				Redeem::Principal(common_fields)
+				| Redeem::Yield(common_fields)
+				| Redeem::PrincipalYield(common_fields) => Some(common_fields),
+				Redeem::All(_) => None,
+			},
+			_ => None,
+		}
+	}
+
+	/// Returns the underlying mint a command operates on, if it names one.
+	fn underlying_mint_address(&self) -> Option<Pubkey> {
+		match self {
+			Commands::Init(
+				Initialize::Tokenizer(common_fields)
+				| Initialize::Mints(common_fields)
+				| Initialize::TokenizerMints(common_fields)
+				| Initialize::Amm(common_fields),
+			)
+			| Commands::Derive(common_fields)
+			| Commands::InitDepositTokenize(InitDepositTokenizeFields {
+				init_fields: common_fields,
+				..
+			}) => Some(common_fields.underlying_mint_address),
+			Commands::Terminate(
+				Terminate::Terminate(common_fields)
+				| Terminate::TerminateTokenizer(common_fields)
+				| Terminate::TerminateMints(common_fields),
+			) => Some(common_fields.underlying_mint_address),
+			command => command
+				.instruction_fields()
+				.and_then(|common_fields| common_fields.underlying_mint_address),
+		}
+	}
+
+	/// Returns the principal or yield mint a redemption's amount is denominated in. The amounts
+	/// of other commands are in the underlying.
+	fn redeemed_mint(&self) -> Option<Pubkey> {
+		match self {
+			Commands::Redeem(Redeem::Principal(common_fields))
+			| Commands::Redeem(Redeem::PrincipalYield(common_fields)) => Some(
+				get_principal_mint_address(
+					&common_fields.lysergic_tokenizer_address,
+					&program_id(),
+				)
+				.0,
+			),
+			Commands::Redeem(Redeem::Yield(common_fields)) => Some(
+				get_yield_mint_address(&common_fields.lysergic_tokenizer_address, &program_id()).0,
+			),
+			_ => None,
+		}
+	}
+
+	/// Mutable counterpart of `instruction_fields`.
+	fn instruction_fields_mut(&mut self) -> Option<&mut InstructionCommonFields> {
+		match self {
+			Commands::Tokenize(tokenize) => match tokenize {
+				Tokenize::Deposit(DepositFields { common_fields, .. })
+				| Tokenize::PrincipalYield(DepositAndTokenizeFields { common_fields, .. })
+				| Tokenize::Principal(common_fields)
+				| Tokenize::Yield(common_fields) => Some(common_fields),
+				Tokenize::Batch(_) => None,
+			},
+			Commands::Redeem(redeem) => match redeem {
+				Redeem::Principal(common_fields)
+				| Redeem::Yield(common_fields)
+				| Redeem::PrincipalYield(common_fields) => Some(common_fields),
+				Redeem::All(_) => None,
+			},
+			_ => None,
+		}
+	}
+}
+
+#[derive(Subcommand, Debug)]
+enum Aliases {
+	/// Add an alias, replacing any existing alias of the same name
+	Add(AliasFields),
+	List,
+	Remove(AliasNameFields),
 }
 
 #[derive(Subcommand, Debug)]
@@ -51,10 +416,12 @@ enum Initialize {
 
 #[derive(Subcommand, Debug)]
 enum Tokenize {
-	Deposit(InstructionCommonFields),
+	Deposit(DepositFields),
 	Principal(InstructionCommonFields),
 	Yield(InstructionCommonFields),
-	PrincipalYield(InstructionCommonFields),
+	PrincipalYield(DepositAndTokenizeFields),
+	/// Deposit and tokenize every row of a `tokenizer,amount,underlying_mint` CSV file
+	Batch(BatchFields),
 }
 
 #[derive(Subcommand, Debug)]
@@ -62,6 +429,8 @@ enum Redeem {
 	Principal(InstructionCommonFields),
 	Yield(InstructionCommonFields),
 	PrincipalYield(InstructionCommonFields),
+	/// Redeem the signer's whole principal position, with matching yield when held
+	All(RedeemAllFields),
 }
 
 #[derive(Subcommand, Debug)]
@@ -80,433 +449,2943 @@ enum Swap {
 
 #[derive(Args, Debug)]
 struct InitializeCommonFields {
+	#[arg(value_parser = parse_pubkey)]
 	underlying_mint_address: Pubkey,
-	expiry: i64,
+	/// Unix timestamp or ISO-8601 date, e.g. `2025-12-31T00:00:00Z`
+	#[arg(required_unless_present = "maturity", value_parser = parse_timestamp)]
+	expiry: Option<i64>,
+	/// Named maturity from the maturity calendar, used in place of `expiry`
+	#[arg(long, conflicts_with = "expiry")]
+	maturity: Option<String>,
+	/// Maturity calendar file, defaults to `~/.config/sclr/maturities.toml`
+	#[arg(long, requires = "maturity")]
+	maturity_calendar: Option<String>,
+}
+
+impl InitializeCommonFields {
+	fn expiry(&self) -> Result<i64> {
+		match (self.expiry, &self.maturity) {
+			(Some(expiry), _) => Ok(expiry),
+			(None, Some(maturity)) => {
+				config::resolve_maturity(self.maturity_calendar.as_deref(), maturity)
+			}
+			(None, None) => Err(anyhow!("Either `expiry` or `--maturity` is required")),
+		}
+	}
+}
+
+#[derive(Args, Debug)]
+struct InitDepositTokenizeFields {
+	#[command(flatten)]
+	init_fields: InitializeCommonFields,
+	/// Amount of underlying to deposit and tokenize
+	#[arg(long)]
+	amount: u64,
 }
 
 #[derive(Args, Debug)]
 struct InstructionCommonFields {
+	#[arg(value_parser = parse_pubkey)]
 	lysergic_tokenizer_address: Pubkey,
-	amount: u64,
-	underlying_mint_address: Pubkey,
+	#[arg(required_unless_present = "ui_amount")]
+	amount: Option<u64>,
+	/// Amount in whole tokens such as `1.5`, scaled by the decimals of the token being moved
+	#[arg(long, conflicts_with = "amount")]
+	ui_amount: Option<String>,
+	/// Underlying mint, read from the tokenizer account when omitted
+	#[arg(long = "underlying-mint", value_parser = parse_pubkey)]
+	underlying_mint_address: Option<Pubkey>,
+	/// Positional form of `--underlying-mint`, kept for existing scripts
+	#[arg(
+		value_parser = parse_pubkey,
+		hide = true,
+		conflicts_with = "underlying_mint_address"
+	)]
+	underlying_mint_positional: Option<Pubkey>,
+	/// Expiry date recorded in the tokenizer, checked against the tokenizer address
+	#[arg(long, value_parser = parse_timestamp)]
+	expiry: Option<i64>,
+}
+
+impl InstructionCommonFields {
+	/// Fields for an instruction built from resolved values rather than parsed arguments, such as
+	/// a batch row or the balances read by `redeem all`.
+	fn new(lysergic_tokenizer_address: Pubkey, underlying_mint: Pubkey, amount: u64) -> Self {
+		Self {
+			lysergic_tokenizer_address,
+			amount: Some(amount),
+			ui_amount: None,
+			underlying_mint_address: Some(underlying_mint),
+			underlying_mint_positional: None,
+			expiry: None,
+		}
+	}
+
+	/// Returns the amount in base units, which `main` resolves from `--ui-amount` if given.
+	fn amount(&self) -> u64 {
+		self.amount.expect("amount is resolved from `--ui-amount`")
+	}
+
+	/// Returns the underlying mint, which `main` resolves before building any instruction.
+	fn underlying_mint_address(&self) -> Pubkey {
+		self.underlying_mint_address
+			.expect("underlying mint is resolved from the tokenizer account")
+	}
+}
+
+#[derive(Args, Debug)]
+struct DepositFields {
+	#[command(flatten)]
+	common_fields: InstructionCommonFields,
+	/// Token account to deposit from instead of the signer's associated token account
+	#[arg(long, value_parser = parse_pubkey)]
+	underlying_source: Option<Pubkey>,
+}
+
+#[derive(Args, Debug)]
+struct DepositAndTokenizeFields {
+	#[command(flatten)]
+	common_fields: InstructionCommonFields,
+	/// Fraction of the deposited amount to tokenize, the remainder stays in the vault
+	#[arg(long, value_parser = parse_fraction)]
+	tokenize_fraction: Option<f64>,
+}
+
+#[derive(Args, Debug)]
+struct RedeemAllFields {
+	#[arg(value_parser = parse_pubkey)]
+	lysergic_tokenizer_address: Pubkey,
+}
+
+#[derive(Args, Debug)]
+struct BatchFields {
+	file: String,
+	/// File recording the rows sent with their signatures. Rerunning the batch with the same
+	/// checkpoint skips the rows whose transaction landed
+	#[arg(long)]
+	checkpoint: Option<String>,
+	/// Report the rows that succeeded, failed and were skipped, the amount tokenized, the fees
+	/// paid and the time taken once the batch finishes
+	#[arg(long)]
+	output_summary: bool,
 }
 
 #[derive(Args, Debug)]
 struct TerminateCommonFields {
+	#[arg(value_parser = parse_pubkey)]
 	lysergic_tokenizer_address: Pubkey,
+	#[arg(value_parser = parse_pubkey)]
 	underlying_mint_address: Pubkey,
 }
 
-fn main() -> Result<()> {
-	let args = Cli::parse();
+/// Resolves the payer signer at `path`: a `usb://ledger` hardware wallet, `-` for a keypair read
+/// from stdin so secrets need not touch disk, or a keypair file.
+fn read_payer_signer(path: &str) -> Result<Box<dyn Signer>, Box<dyn std::error::Error>> {
+	if path.starts_with("usb://") {
+		return Ok(Box::new(ledger_keypair(path)?));
+	}
+
+	Ok(Box::new(read_keypair_source(path)?))
+}
 
-	let solana_config_file = if let Some(ref config) = *solana_cli_config::CONFIG_FILE {
-		solana_cli_config::Config::load(config).unwrap_or_default()
+/// Reads the keypair file at `path`, or the keypair JSON on stdin when `path` is `-`.
+fn read_keypair_source(path: &str) -> Result<Keypair, Box<dyn std::error::Error>> {
+	if path == "-" {
+		stdin_keypair()
 	} else {
-		solana_cli_config::Config::default()
-	};
+		read_keypair_file(path)
+	}
+}
 
-	let wallet_keypair = read_keypair_file(&solana_config_file.keypair_path)
-		.map_err(|err| anyhow!("Unable to read keypair file: {}", err))?;
-	let wallet_pubkey = wallet_keypair.pubkey();
+/// Returns the keypair given on stdin. Stdin can only be consumed once, so the first read is kept
+/// for every later load of `-` in the same run.
+fn stdin_keypair() -> Result<Keypair, Box<dyn std::error::Error>> {
+	static STDIN_KEYPAIR: OnceLock<Result<[u8; 64], String>> = OnceLock::new();
 
-	let client = RpcClient::new_with_commitment(
-		solana_config_file.json_rpc_url.to_string(),
-		CommitmentConfig::confirmed(),
-	);
+	let keypair_bytes = STDIN_KEYPAIR.get_or_init(|| {
+		read_keypair(&mut io::stdin().lock())
+			.map(|keypair| keypair.to_bytes())
+			.map_err(|err| err.to_string())
+	});
 
-	let slot = client.get_slot()?;
-	let timestamp = client.get_block_time(slot)? as i64;
+	match keypair_bytes {
+		Ok(bytes) => Ok(Keypair::from_bytes(bytes)?),
+		Err(err) => Err(err.clone().into()),
+	}
+}
 
-	let instruction: Instruction = match args.cmd {
-		Commands::Init(init) => match init {
-			Initialize::Tokenizer(common_fields) => {
-				let expiry = Expiry::from_i64(common_fields.expiry).map_err(|err| {
-					anyhow!("Unable to parse the given value to `Expiry`: {}", err)
-				})?;
+/// Connects to the Ledger at `path`, such as `usb://ledger?key=0/0`. Signing waits for the
+/// transaction to be approved on the device.
+fn ledger_keypair(path: &str) -> Result<RemoteKeypair, Box<dyn std::error::Error>> {
+	let (locator, derivation_path) = match path.split_once("?key=") {
+		Some((locator, key)) => (locator, DerivationPath::from_key_str(key)?),
+		None => (path, DerivationPath::default()),
+	};
+	let locator = Locator::new_from_path(locator)?;
 
-				let expiry_date = expiry.to_expiry_date(timestamp).expect("Invalid");
+	let wallet_manager = maybe_wallet_manager()?
+		.ok_or("No hardware wallet found, connect and unlock the Ledger and open its Solana app")?;
 
-				let (lysergic_tokenizer_address, _) =
-					get_tokenizer_address(&common_fields.underlying_mint_address, expiry_date);
+	Ok(generate_remote_keypair(
+		locator,
+		derivation_path,
+		&wallet_manager,
+		false,
+		"payer",
+	)?)
+}
 
-				let underlying_vault_address =
-					spl_associated_token_account::get_associated_token_address(
-						&lysergic_tokenizer_address,
-						&common_fields.underlying_mint_address,
-					);
+/// Address aliases, loaded the first time a value is not a pubkey itself.
+static ALIASES: OnceLock<Result<BTreeMap<String, String>, String>> = OnceLock::new();
 
-				let (principal_mint_address, _) =
-					get_principal_mint_address(&lysergic_tokenizer_address);
+fn parse_pubkey(value: &str) -> Result<Pubkey, String> {
+	if let Ok(pubkey) = Pubkey::from_str(value) {
+		return Ok(pubkey);
+	}
 
-				let (yield_mint_address, _) = get_yield_mint_address(&lysergic_tokenizer_address);
+	let aliases = ALIASES
+		.get_or_init(|| config::load_aliases().map_err(|err| err.to_string()))
+		.as_ref()
+		.map_err(Clone::clone)?;
+	let value = aliases.get(value).map(String::as_str).unwrap_or(value);
 
-				//TODO: Calculation methodology for the fixed APY of the principal token
-				//NOTE: placeholder
-				let fixed_apy = 0;
+	Pubkey::from_str(value).map_err(|err| match err {
+		ParsePubkeyError::WrongSize => format!(
+			"invalid base58 pubkey: expected 32 bytes, check that `{}` has not been truncated",
+			value
+		),
+		ParsePubkeyError::Invalid => format!(
+			"invalid base58 pubkey: `{}` contains characters outside the base58 alphabet",
+			value
+		),
+	})
+}
 
-				println!("Tokenizer Address: {}", lysergic_tokenizer_address);
-				println!("Vault Address: {}", underlying_vault_address);
-				println!("Principal Mint Address: {}", principal_mint_address);
-				println!("Yield Mint Address: {}", yield_mint_address);
+fn parse_fraction(value: &str) -> Result<f64, String> {
+	let fraction: f64 = value
+		.parse()
+		.map_err(|err| format!("invalid fraction `{}`: {}", value, err))?;
 
-				instruction::init_tokenizer(
-					&lysergic_tokenizer_address,
-					&wallet_pubkey,
-					&underlying_vault_address,
-					&common_fields.underlying_mint_address,
-					&principal_mint_address,
-					&yield_mint_address,
-					expiry,
-					fixed_apy,
-				)
-				.map_err(|err| anyhow!("Unable to create init instruction: {}", err))?
-			}
-			Initialize::Mints(common_fields) => {
-				let expiry = Expiry::from_i64(common_fields.expiry).map_err(|err| {
-					anyhow!("Unable to parse the given value to `Expiry`: {}", err)
-				})?;
+	if fraction > 0.0 && fraction <= 1.0 {
+		Ok(fraction)
+	} else {
+		Err(format!("fraction must be within (0, 1], got {}", fraction))
+	}
+}
 
-				let expiry_date = expiry
-					.to_expiry_date(timestamp)
-					.expect("Unable to convert expiry to expiry date");
+fn parse_memo(value: &str) -> Result<String, String> {
+	// Longer memos no longer fit in a transaction alongside a signature
+	const MAX_MEMO_LEN: usize = 566;
 
-				let (lysergic_tokenizer_address, _) =
-					get_tokenizer_address(&common_fields.underlying_mint_address, expiry_date);
+	if value.len() > MAX_MEMO_LEN {
+		return Err(format!(
+			"memo is {} bytes, the memo program accepts at most {}",
+			value.len(),
+			MAX_MEMO_LEN
+		));
+	}
 
-				let (principal_mint_address, _) =
-					get_principal_mint_address(&lysergic_tokenizer_address);
+	Ok(value.to_string())
+}
 
-				let (yield_mint_address, _) = get_yield_mint_address(&lysergic_tokenizer_address);
+/// Parses a Unix timestamp or ISO-8601 date. Whether an expiry lies in the future is checked
+/// against the configured time source once the command runs, not here.
+fn parse_timestamp(value: &str) -> Result<i64, String> {
+	if let Ok(timestamp) = value.parse::<i64>() {
+		return Ok(timestamp);
+	}
 
-				println!("Principal Mint Address: {}", principal_mint_address);
-				println!("Yield Mint Address: {}", yield_mint_address);
+	DateTime::parse_from_rfc3339(value)
+		.map(|date| date.timestamp())
+		.map_err(|err| format!("invalid expiry `{}`: {}", value, err))
+}
 
-				instruction::init_mints(
-					&lysergic_tokenizer_address,
-					&wallet_pubkey,
-					&common_fields.underlying_mint_address,
-					&principal_mint_address,
-					&yield_mint_address,
-					expiry,
-				)
-				.map_err(|err| anyhow!("Unable to create `Initialize` instruction: {}", err))?
-			}
-			Initialize::TokenizerMints(common_fields) => {
-				let expiry = Expiry::from_i64(common_fields.expiry).map_err(|err| {
-					anyhow!("Unable to parse the given value to `Expiry`: {}", err)
-				})?;
+/// Converts a decimal amount of whole tokens into base units of a mint with `decimals`,
+/// rejecting amounts more precise than the mint can represent.
+fn parse_ui_amount(ui_amount: &str, decimals: u8) -> Result<u64> {
+	let (whole, fraction) = ui_amount.split_once('.').unwrap_or((ui_amount, ""));
 
-				let expiry_date = expiry.to_expiry_date(timestamp).expect("Invalid");
-				let (lysergic_tokenizer_address, _) =
-					get_tokenizer_address(&common_fields.underlying_mint_address, expiry_date);
+	if fraction.len() > decimals as usize {
+		return Err(anyhow!(
+			"Amount {} has more than the mint's {} decimals",
+			ui_amount,
+			decimals
+		));
+	}
 
-				let underlying_vault_address =
-					spl_associated_token_account::get_associated_token_address(
-						&lysergic_tokenizer_address,
-						&common_fields.underlying_mint_address,
-					);
+	let digits = format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+	if (whole.is_empty() && fraction.is_empty())
+		|| !digits.bytes().all(|byte| byte.is_ascii_digit())
+	{
+		return Err(anyhow!("Invalid amount `{}`", ui_amount));
+	}
 
-				let (principal_mint_address, _) =
-					get_principal_mint_address(&lysergic_tokenizer_address);
+	digits
+		.parse()
+		.map_err(|err| anyhow!("Invalid amount `{}`: {}", ui_amount, err))
+}
 
-				let (yield_mint_address, _) = get_yield_mint_address(&lysergic_tokenizer_address);
+/// Scales `amount` by `fraction`, rounding down to whole base units.
+fn apply_fraction(amount: u64, fraction: f64) -> u64 {
+	const PRECISION: u128 = 1_000_000_000;
 
-				//TODO: Calculation methodology for the fixed APY of the principal token
-				//NOTE: placeholder
-				let fixed_apy = 0;
+	let scaled_fraction = (fraction * PRECISION as f64).round() as u128;
+	(amount as u128 * scaled_fraction / PRECISION) as u64
+}
 
-				println!("Tokenizer Address: {}", lysergic_tokenizer_address);
-				println!("Vault Address: {}", underlying_vault_address);
-				println!("Principal Mint Address: {}", principal_mint_address);
-				println!("Yield Mint Address: {}", yield_mint_address);
+fn parse_presigner(value: &str) -> Result<(Pubkey, Signature), String> {
+	let (pubkey, signature) = value
+		.split_once('=')
+		.ok_or_else(|| format!("expected `PUBKEY=SIGNATURE`, got `{}`", value))?;
 
-				instruction::init_tokenizer_and_mints(
-					&lysergic_tokenizer_address,
-					&wallet_pubkey,
-					&underlying_vault_address,
-					&common_fields.underlying_mint_address,
-					&principal_mint_address,
-					&yield_mint_address,
-					Expiry::from_i64(common_fields.expiry)?,
-					fixed_apy,
-				)
-				.map_err(|err| {
-					anyhow!(
-						"Unable to create `InitializeTokenizerAndMints` instruction: {}",
-						err
-					)
-				})?
-			}
-			Initialize::Amm(_common_fields) => unimplemented!(),
-		},
-		Commands::Tokenize(tokenize) => match tokenize {
-			Tokenize::Deposit(common_fields) => {
-				let underlying_vault = spl_associated_token_account::get_associated_token_address(
-					&common_fields.lysergic_tokenizer_address,
-					&common_fields.underlying_mint_address,
-				);
+	let signature = Signature::from_str(signature)
+		.map_err(|err| format!("invalid signature `{}`: {}", signature, err))?;
 
-				instruction::deposit_underlying(
-					&common_fields.lysergic_tokenizer_address,
-					&wallet_pubkey,
-					&underlying_vault,
-					&common_fields.underlying_mint_address,
-					common_fields.amount,
-				)
-				.map_err(|err| anyhow!("Unable to create `Deposit` instruction: {}", err))?
-			}
-			Tokenize::Principal(common_fields) => {
-				let (principal_mint_address, _) =
-					get_principal_mint_address(&common_fields.lysergic_tokenizer_address);
+	Ok((parse_pubkey(pubkey)?, signature))
+}
 
-				let user_principal_token_address =
-					spl_associated_token_account::get_associated_token_address(
-						&wallet_pubkey,
-						&principal_mint_address,
-					);
+#[derive(Clone, Debug, Default)]
+struct ComputeBudget {
+	unit_limit: Option<u32>,
+	unit_price: Option<u64>,
+}
 
-				instruction::tokenize_principal(
-					&common_fields.lysergic_tokenizer_address,
-					&principal_mint_address,
-					&wallet_pubkey,
-					&user_principal_token_address,
-					common_fields.amount,
-				)
-				.map_err(|err| {
-					anyhow!("Unable to create `TokenizePrincipal` instruction: {}", err)
-				})?
-			}
-			Tokenize::Yield(common_fields) => {
-				let (yield_mint_address, _) =
-					get_yield_mint_address(&common_fields.lysergic_tokenizer_address);
+impl ComputeBudget {
+	fn instructions(&self) -> Vec<Instruction> {
+		let mut instructions = Vec::new();
 
-				let user_yield_token_address =
-					spl_associated_token_account::get_associated_token_address(
-						&wallet_pubkey,
-						&yield_mint_address,
-					);
+		if let Some(unit_limit) = self.unit_limit {
+			instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(unit_limit));
+		}
 
-				instruction::tokenize_yield(
-					&common_fields.lysergic_tokenizer_address,
-					&yield_mint_address,
-					&wallet_pubkey,
-					&user_yield_token_address,
-					common_fields.amount,
-				)
-				.map_err(|err| anyhow!("Unable to create `TokenizeYield` instruction: {}", err))?
-			}
-			Tokenize::PrincipalYield(common_fields) => {
-				let underlying_vault = spl_associated_token_account::get_associated_token_address(
-					&common_fields.lysergic_tokenizer_address,
-					&common_fields.underlying_mint_address,
-				);
+		if let Some(unit_price) = self.unit_price {
+			instructions.push(ComputeBudgetInstruction::set_compute_unit_price(unit_price));
+		}
 
-				println!("Underlying vault: {}", underlying_vault);
+		instructions
+	}
+}
 
-				let (principal_mint_address, _) =
-					get_principal_mint_address(&common_fields.lysergic_tokenizer_address);
+/// Options applied to every transaction the CLI sends, read from the command line before the
+/// subcommand is consumed so commands sending several transactions can share them.
+struct TransactionOptions {
+	compute_budget: ComputeBudget,
+	memo: Option<String>,
+	max_fee: Option<u64>,
+	max_retries: u32,
+	max_sign_attempts: u32,
+	skip_preflight: bool,
+	dry_run: bool,
+	simulate_at_slot: Option<Slot>,
+	success_on: SuccessOn,
+	ws_url: Option<String>,
+	broadcast_to: Vec<String>,
+	client_id: Option<String>,
+	rpc_timeout: Option<Duration>,
+}
 
-				let (yield_mint_address, _) =
-					get_yield_mint_address(&common_fields.lysergic_tokenizer_address);
+impl TransactionOptions {
+	fn new(args: &Cli) -> Self {
+		Self {
+			compute_budget: args.compute_budget.clone().unwrap_or(ComputeBudget {
+				unit_limit: args.compute_unit_limit,
+				unit_price: args.compute_unit_price,
+			}),
+			memo: args.memo.clone(),
+			max_fee: args.max_fee,
+			max_retries: args.max_retries,
+			max_sign_attempts: args.max_sign_attempts,
+			skip_preflight: args.skip_preflight,
+			dry_run: args.dry_run,
+			simulate_at_slot: args.simulate_at_slot,
+			success_on: args.success_on,
+			ws_url: args.ws_url.clone(),
+			broadcast_to: args.broadcast_to.clone(),
+			client_id: args.client_id.clone().flatten(),
+			rpc_timeout: args.rpc_timeout.map(Duration::from_secs),
+		}
+	}
 
-				let user_underlying_token_address =
-					spl_associated_token_account::get_associated_token_address(
-						&wallet_pubkey,
-						&common_fields.underlying_mint_address,
-					);
+	/// Adds the requested compute budget instructions ahead of `instructions` and the memo,
+	/// signed by `fee_payer`, after them.
+	fn wrap(&self, fee_payer: &Pubkey, instructions: &mut Vec<Instruction>) {
+		instructions.splice(0..0, self.compute_budget.instructions());
 
-				let user_principal_token_address =
-					spl_associated_token_account::get_associated_token_address(
-						&wallet_pubkey,
-						&principal_mint_address,
-					);
+		if let Some(ref memo) = self.memo {
+			instructions.push(spl_memo::build_memo(memo.as_bytes(), &[fee_payer]));
+		}
+	}
 
-				let user_yield_token_address =
-					spl_associated_token_account::get_associated_token_address(
-						&wallet_pubkey,
-						&yield_mint_address,
-					);
+	/// Sends the signed `transaction` and awaits it the way every command does: through every
+	/// `--broadcast-to` endpoint, over `--ws-url`, or with retries, re-signing it with `signers`
+	/// once its blockhash expires when `refresh_blockhash` is set. `--success-on accepted` only
+	/// awaits the node accepting it. Reports the signature and how it landed.
+	fn send(
+		&self,
+		client: &impl RpcProvider,
+		transaction: &mut Transaction,
+		signers: &[&dyn Signer],
+		refresh_blockhash: bool,
+		report: &mut output::Report,
+	) -> Result<Signature> {
+		if self.success_on == SuccessOn::Accepted {
+			let signature = send_transaction_with_retries(
+				client,
+				transaction,
+				self.max_retries,
+				self.skip_preflight,
+			)?;
+			report.field("Signature", signature);
+			return Ok(signature);
+		}
 
-				instruction::deposit_and_tokenize(
-					&common_fields.lysergic_tokenizer_address,
-					&underlying_vault,
-					&principal_mint_address,
-					&yield_mint_address,
-					&wallet_pubkey,
-					&user_underlying_token_address,
-					&user_principal_token_address,
-					&user_yield_token_address,
-					common_fields.amount,
-				)
-				.map_err(|err| {
-					anyhow!("Unable to create `DepositAndTokenize` instruction: {}", err)
-				})?
+		let spinner = confirmation_spinner(report.is_json());
+		let (signature, attempts) = if !self.broadcast_to.is_empty() {
+			let mut urls = vec![client.url()];
+			urls.extend(self.broadcast_to.iter().cloned());
+			let (signature, url) =
+				broadcast_and_confirm(urls, client.commitment(), self, transaction)?;
+			report.field("Confirmed via", url);
+			(signature, None)
+		} else {
+			match self.ws_url {
+				Some(ref ws_url) => (
+					send_and_confirm_with_websocket(
+						client,
+						ws_url,
+						transaction,
+						self.max_retries,
+						self.skip_preflight,
+					)?,
+					None,
+				),
+				None => {
+					let (signature, attempts) = send_with_retries(
+						client,
+						transaction,
+						signers,
+						self.max_retries,
+						self.max_sign_attempts,
+						self.skip_preflight,
+						refresh_blockhash,
+					)?;
+					(signature, Some(attempts))
+				}
 			}
-		},
-		Commands::Redeem(redeem) => match redeem {
-			Redeem::Principal(common_fields) => {
-				let underlying_vault_address =
-					spl_associated_token_account::get_associated_token_address(
-						&common_fields.lysergic_tokenizer_address,
-						&common_fields.underlying_mint_address,
-					);
+		};
+		drop(spinner);
 
-				let (principal_mint_address, _) =
-					get_principal_mint_address(&common_fields.lysergic_tokenizer_address);
+		if let Some(attempts) = attempts {
+			report.field("Attempts", attempts);
+		}
+		report.field("Signature", signature);
 
-				let user_underlying_token_address =
-					spl_associated_token_account::get_associated_token_address(
-						&wallet_pubkey,
-						&common_fields.underlying_mint_address,
-					);
+		Ok(signature)
+	}
+}
 
-				let user_principal_token_address =
-					spl_associated_token_account::get_associated_token_address(
-						&wallet_pubkey,
-						&principal_mint_address,
-					);
+impl FromStr for ComputeBudget {
+	type Err = anyhow::Error;
 
-				instruction::redeem_mature_principal(
-					&common_fields.lysergic_tokenizer_address,
-					&underlying_vault_address,
-					&common_fields.underlying_mint_address,
-					&principal_mint_address,
-					&wallet_pubkey,
-					&user_underlying_token_address,
-					&user_principal_token_address,
-					common_fields.amount,
-				)
-				.map_err(|err| {
-					anyhow!(
-						"Unable to create `RedeemPrincipalOnly` instruction: {}",
-						err
-					)
-				})?
+	fn from_str(s: &str) -> Result<Self> {
+		let mut compute_budget = ComputeBudget::default();
+
+		for entry in s.split(',') {
+			let (key, value) = entry
+				.split_once('=')
+				.ok_or_else(|| anyhow!("Expected `key=value`, got `{}`", entry))?;
+
+			match key.trim() {
+				"limit" => {
+					compute_budget.unit_limit = Some(value.trim().parse().map_err(|err| {
+						anyhow!("Invalid compute unit limit `{}`: {}", value, err)
+					})?)
+				}
+				"price" => {
+					compute_budget.unit_price = Some(value.trim().parse().map_err(|err| {
+						anyhow!("Invalid compute unit price `{}`: {}", value, err)
+					})?)
+				}
+				key => {
+					return Err(anyhow!(
+						"Unknown compute budget key `{}`, expected `limit` or `price`",
+						key
+					))
+				}
 			}
-			Redeem::Yield(common_fields) => {
-				let (yield_mint_address, _) =
-					get_yield_mint_address(&common_fields.lysergic_tokenizer_address);
+		}
 
-				let user_underlying_token_address =
-					spl_associated_token_account::get_associated_token_address(
-						&wallet_pubkey,
-						&common_fields.underlying_mint_address,
-					);
+		Ok(compute_budget)
+	}
+}
 
-				let user_yield_token_address =
-					spl_associated_token_account::get_associated_token_address(
-						&wallet_pubkey,
-						&yield_mint_address,
-					);
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum SuccessOn {
+	/// The RPC node accepted the transaction
+	Accepted,
+	/// The transaction reached `confirmed` commitment
+	Confirmed,
+	/// The transaction reached `finalized` commitment
+	Finalized,
+}
 
-				instruction::claim_yield(
-					&common_fields.lysergic_tokenizer_address,
-					&common_fields.underlying_mint_address,
-					&yield_mint_address,
-					&wallet_pubkey,
-					&user_underlying_token_address,
-					&user_yield_token_address,
-					common_fields.amount,
-				)
-				.map_err(|err| anyhow!("Unable to create `ClaimYield` instruction: {}", err))?
+#[derive(Clone, Debug, Default)]
+enum TimeSource {
+	Local,
+	#[default]
+	Rpc,
+	Fixed(i64),
+}
+
+impl TimeSource {
+	fn now(&self, client: &impl RpcProvider) -> Result<i64> {
+		match self {
+			TimeSource::Local => Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64),
+			TimeSource::Rpc => {
+				let slot = client.get_slot()?;
+				Ok(client.get_block_time(slot)?)
 			}
-			Redeem::PrincipalYield(common_fields) => {
-				let underlying_vault_address =
-					spl_associated_token_account::get_associated_token_address(
-						&common_fields.lysergic_tokenizer_address,
-						&common_fields.underlying_mint_address,
-					);
+			TimeSource::Fixed(timestamp) => Ok(*timestamp),
+		}
+	}
+}
 
-				let (principal_mint_address, _) =
-					get_principal_mint_address(&common_fields.lysergic_tokenizer_address);
+impl FromStr for TimeSource {
+	type Err = anyhow::Error;
 
-				let (yield_mint_address, _) =
-					get_yield_mint_address(&common_fields.lysergic_tokenizer_address);
+	fn from_str(s: &str) -> Result<Self> {
+		match s {
+			"local" => Ok(TimeSource::Local),
+			"rpc" => Ok(TimeSource::Rpc),
+			_ => match s.strip_prefix("fixed:") {
+				Some(timestamp) => timestamp
+					.parse()
+					.map(TimeSource::Fixed)
+					.map_err(|err| anyhow!("Invalid fixed timestamp `{}`: {}", timestamp, err)),
+				None => Err(anyhow!(
+					"Unknown time source `{}`, expected `local`, `rpc` or `fixed:<timestamp>`",
+					s
+				)),
+			},
+		}
+	}
+}
 
-				let user_underlying_token_address =
-					spl_associated_token_account::get_associated_token_address(
-						&wallet_pubkey,
-						&common_fields.underlying_mint_address,
-					);
+#[derive(Args, Debug)]
+struct ReplayFields {
+	signature: Signature,
+}
 
-				let user_principal_token_address =
-					spl_associated_token_account::get_associated_token_address(
-						&wallet_pubkey,
-						&principal_mint_address,
-					);
+#[derive(Args, Debug)]
+struct AliasFields {
+	name: String,
+	#[arg(value_parser = parse_pubkey)]
+	address: Pubkey,
+}
 
-				let user_yield_token_address =
-					spl_associated_token_account::get_associated_token_address(
-						&wallet_pubkey,
-						&yield_mint_address,
-					);
+#[derive(Args, Debug)]
+struct AliasNameFields {
+	name: String,
+}
 
-				instruction::redeem_principal_and_yield(
-					&common_fields.lysergic_tokenizer_address,
-					&underlying_vault_address,
-					&common_fields.underlying_mint_address,
-					&principal_mint_address,
-					&yield_mint_address,
-					&wallet_pubkey,
-					&user_underlying_token_address,
-					&user_principal_token_address,
-					&user_yield_token_address,
-					common_fields.amount,
-				)
-				.map_err(|err| {
-					anyhow!(
-						"Unable to create `RedeemPrincipalAndYield` instruction: {}",
-						err
-					)
-				})?
-			}
-		},
-		Commands::Terminate(terminate) => match terminate {
-			Terminate::Terminate(common_fields) => {
-				let (principal_mint_address, _) =
-					get_principal_mint_address(&common_fields.lysergic_tokenizer_address);
-				let (yield_mint_address, _) =
-					get_yield_mint_address(&common_fields.lysergic_tokenizer_address);
-				instruction::terminate(
-					&common_fields.lysergic_tokenizer_address,
-					&wallet_pubkey,
-					&spl_associated_token_account::get_associated_token_address(
-						&common_fields.lysergic_tokenizer_address,
-						&common_fields.underlying_mint_address,
-					),
-					&spl_associated_token_account::get_associated_token_address(
-						&common_fields.lysergic_tokenizer_address,
-						&principal_mint_address,
-					),
-					&spl_associated_token_account::get_associated_token_address(
-						&common_fields.lysergic_tokenizer_address,
-						&yield_mint_address,
-					),
+#[derive(Args, Debug)]
+struct SubmitFields {
+	/// Base64-encoded signed transaction
+	transaction: String,
+}
+
+#[derive(Args, Debug)]
+struct DecodeTxFields {
+	/// Base64-encoded transaction, signed or not
+	transaction: String,
+}
+
+#[derive(Args, Debug)]
+struct WatchFields {
+	#[arg(value_parser = parse_pubkey)]
+	lysergic_tokenizer_address: Pubkey,
+	/// Seconds between polls
+	#[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+	interval: u64,
+	/// Most RPC reads a poll has in flight at once, 1 reads one after another
+	#[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+	max_in_flight: u64,
+}
+
+#[derive(Args, Debug)]
+struct ListMarketsFields {
+	/// Only list tokenizers of this underlying mint
+	#[arg(long = "underlying-mint", value_parser = parse_pubkey)]
+	underlying_mint_address: Option<Pubkey>,
+	/// Skip this many markets of the list ordered by expiry
+	#[arg(long, default_value_t = 0)]
+	offset: usize,
+	/// List at most this many markets
+	#[arg(long)]
+	limit: Option<usize>,
+}
+
+#[derive(Args, Debug)]
+struct StatusFields {
+	#[arg(value_parser = parse_pubkey)]
+	lysergic_tokenizer_address: Pubkey,
+}
+
+#[derive(Args, Debug)]
+struct BalancesFields {
+	#[arg(value_parser = parse_pubkey)]
+	lysergic_tokenizer_address: Pubkey,
+	/// Wallet to check, defaults to the configured keypair
+	#[arg(long, value_parser = parse_pubkey)]
+	owner: Option<Pubkey>,
+}
+
+#[derive(Args, Debug)]
+struct PositionsFields {
+	/// Wallet to scan, defaults to the configured keypair
+	#[arg(long, value_parser = parse_pubkey)]
+	owner: Option<Pubkey>,
+}
+
+/// Builds a client for `urls`, failing over from each endpoint to the next when there are
+/// several.
+fn new_rpc_client(
+	mut urls: Vec<String>,
+	commitment: CommitmentConfig,
+	client_id: Option<&str>,
+	timeout: Option<Duration>,
+) -> Result<RpcClient> {
+	if urls.len() == 1 && client_id.is_none() {
+		let url = urls.remove(0);
+		return Ok(match timeout {
+			Some(timeout) => RpcClient::new_with_timeout_and_commitment(url, timeout, commitment),
+			None => RpcClient::new_with_commitment(url, commitment),
+		});
+	}
+
+	let mut headers = HttpSender::default_headers();
+	if let Some(client_id) = client_id {
+		headers.insert(
+			HeaderName::from_static("x-client-id"),
+			HeaderValue::from_str(client_id)
+				.map_err(|err| anyhow!("Invalid client id `{}`: {}", client_id, err))?,
+		);
+	}
+
+	let http_client = reqwest::Client::builder()
+		.default_headers(headers)
+		.timeout(timeout.unwrap_or(Duration::from_secs(30)))
+		.build()
+		.map_err(|err| anyhow!("Unable to build HTTP client: {}", err))?;
+
+	let mut senders: Vec<HttpSender> = urls
+		.into_iter()
+		.map(|url| HttpSender::new_with_client(url, http_client.clone()))
+		.collect();
+	let config = RpcClientConfig::with_commitment(commitment);
+
+	Ok(if senders.len() == 1 {
+		RpcClient::new_sender(senders.remove(0), config)
+	} else {
+		RpcClient::new_sender(failover::FailoverSender::new(senders), config)
+	})
+}
+
+fn trace_derivations(
+	underlying_mint_address: &Pubkey,
+	expiry_date: i64,
+	underlying_token_program: &Pubkey,
+	report: &output::Report,
+) {
+	let (tokenizer_address, tokenizer_bump) =
+		get_tokenizer_address(underlying_mint_address, expiry_date, &program_id());
+	report.line(format_args!("Tokenizer Address: {}", tokenizer_address));
+	report.line(format_args!("  Program: {}", program_id()));
+	report.line(format_args!(
+		"  Underlying Mint: {}",
+		underlying_mint_address
+	));
+	report.line(format_args!("  Expiry Date: {}", expiry_date));
+	report.line(format_args!("  Bump: {}", tokenizer_bump));
+
+	let underlying_vault_address =
+		spl_associated_token_account::get_associated_token_address_with_program_id(
+			&tokenizer_address,
+			underlying_mint_address,
+			underlying_token_program,
+		);
+	report.line(format_args!("Vault Address: {}", underlying_vault_address));
+	report.line("  Associated token account of the tokenizer for the underlying mint");
+	report.line(format_args!(
+		"  Token Program: {}",
+		underlying_token_program
+	));
+
+	let (principal_mint_address, principal_mint_bump) =
+		get_principal_mint_address(&tokenizer_address, &program_id());
+	report.line(format_args!(
+		"Principal Mint Address: {}",
+		principal_mint_address
+	));
+	report.line(format_args!("  Tokenizer: {}", tokenizer_address));
+	report.line(format_args!("  Bump: {}", principal_mint_bump));
+
+	let (yield_mint_address, yield_mint_bump) =
+		get_yield_mint_address(&tokenizer_address, &program_id());
+	report.line(format_args!("Yield Mint Address: {}", yield_mint_address));
+	report.line(format_args!("  Tokenizer: {}", tokenizer_address));
+	report.line(format_args!("  Bump: {}", yield_mint_bump));
+}
+
+fn print_account_keys(message: &Message, report: &mut output::Report) {
+	report.list(
+		"Signers",
+		message
+			.account_keys
+			.iter()
+			.enumerate()
+			.filter(|(index, _)| message.is_signer(*index))
+			.map(|(_, pubkey)| pubkey),
+	);
+
+	report.list(
+		"Writable",
+		message
+			.account_keys
+			.iter()
+			.enumerate()
+			.filter(|(index, _)| message.is_maybe_writable(*index, None))
+			.map(|(_, pubkey)| pubkey),
+	);
+}
+
+/// Estimates the cost of `transaction`. Rent is the lamports held after simulation by the
+/// writable accounts that do not exist yet, i.e. the accounts the transaction creates.
+fn estimate(
+	client: &impl RpcProvider,
+	transaction: &Transaction,
+	report: &mut output::Report,
+) -> Result<()> {
+	let message = &transaction.message;
+
+	let fee = client
+		.get_fee_for_message(message)
+		.map_err(|err| anyhow!("Unable to get fee for message: {}", err))?;
+
+	let writable_addresses: Vec<Pubkey> = message
+		.account_keys
+		.iter()
+		.enumerate()
+		.filter(|(index, _)| message.is_maybe_writable(*index, None))
+		.map(|(_, pubkey)| *pubkey)
+		.collect();
+
+	let new_addresses: Vec<String> = client
+		.get_multiple_accounts(&writable_addresses)
+		.map_err(|err| anyhow!("Unable to fetch accounts: {}", err))?
+		.iter()
+		.zip(&writable_addresses)
+		.filter(|(account, _)| account.is_none())
+		.map(|(_, pubkey)| pubkey.to_string())
+		.collect();
+
+	let simulation = client
+		.simulate_transaction_with_config(
+			transaction,
+			RpcSimulateTransactionConfig {
+				sig_verify: false,
+				replace_recent_blockhash: true,
+				accounts: Some(RpcSimulateTransactionAccountsConfig {
+					encoding: Some(UiAccountEncoding::Base64),
+					addresses: new_addresses,
+				}),
+				..RpcSimulateTransactionConfig::default()
+			},
+		)
+		.map_err(|err| anyhow!("Unable to simulate transaction: {}", err))?
+		.value;
+
+	let rent: u64 = simulation
+		.accounts
+		.unwrap_or_default()
+		.iter()
+		.flatten()
+		.map(|account| account.lamports)
+		.sum();
+
+	report.field("Fee", format!("{} lamports", fee));
+	report.field("Rent", format!("{} lamports", rent));
+	report.field(
+		"Compute Units",
+		simulation.units_consumed.unwrap_or_default(),
+	);
+
+	if let Some(err) = simulation.err {
+		report.field("Simulation Error", err);
+	}
+
+	Ok(())
+}
+
+/// Simulates `transaction` without signature verification, printing its logs and compute
+/// usage. The blockhash is replaced so the result does not depend on when it was fetched, and
+/// `min_context_slot` keeps the simulation from running against state older than that slot.
+fn simulate(
+	client: &impl RpcProvider,
+	transaction: &Transaction,
+	min_context_slot: Option<Slot>,
+	report: &mut output::Report,
+) -> Result<()> {
+	let response = client
+		.simulate_transaction_with_config(
+			transaction,
+			RpcSimulateTransactionConfig {
+				sig_verify: false,
+				replace_recent_blockhash: true,
+				commitment: Some(client.commitment()),
+				min_context_slot,
+				..RpcSimulateTransactionConfig::default()
+			},
+		)
+		.map_err(|err| anyhow!("Unable to simulate transaction: {}", err))?;
+
+	report.field("Simulated at slot", response.context.slot);
+
+	let simulation = response.value;
+	report.list("Logs", simulation.logs.unwrap_or_default());
+	report.field(
+		"Compute Units",
+		simulation.units_consumed.unwrap_or_default(),
+	);
+
+	match simulation.err {
+		Some(err) => Err(anyhow!(
+			"Simulation failed: {}",
+			decode::describe_transaction_error(&err, &transaction.message)
+		)),
+		None => Ok(()),
+	}
+}
+
+fn print_instruction_data(instructions: &[Instruction], report: &output::Report) -> Result<()> {
+	for instruction in instructions {
+		let data_hex: String = instruction
+			.data
+			.iter()
+			.map(|byte| format!("{:02x}", byte))
+			.collect();
+
+		report.line(format_args!("Program: {}", instruction.program_id));
+		report.line(format_args!("  Data: {}", data_hex));
+
+		if instruction.program_id == program_id() {
+			report.line(format_args!(
+				"  Decoded: {:?}",
+				decode::decode_instruction(&instruction.data)?
+			));
+		}
+	}
+
+	Ok(())
+}
+
+fn print_instruction_accounts(instruction: &Instruction, report: &output::Report) {
+	report.line(format_args!("Program: {}", instruction.program_id));
+	for (index, account) in instruction.accounts.iter().enumerate() {
+		report.line(format_args!(
+			"  {}: {} signer: {} writable: {}",
+			index, account.pubkey, account.is_signer, account.is_writable
+		));
+	}
+}
+
+fn print_sign_only(transaction: &Transaction, report: &mut output::Report) {
+	report.field("Blockhash", transaction.message.recent_blockhash);
+
+	let (present, absent): (Vec<_>, Vec<_>) = transaction
+		.message
+		.account_keys
+		.iter()
+		.zip(&transaction.signatures)
+		.partition(|(_, signature)| **signature != Signature::default());
+
+	report.list(
+		"Signers",
+		present
+			.iter()
+			.map(|(pubkey, signature)| format!("{}={}", pubkey, signature)),
+	);
+
+	if !absent.is_empty() {
+		report.list("Absent Signers", absent.iter().map(|(pubkey, _)| pubkey));
+	}
+}
+
+fn print_compiled_message(message: &Message, report: &output::Report) {
+	report.line("Header:");
+	report.line(format_args!(
+		"  Required Signatures: {}",
+		message.header.num_required_signatures
+	));
+	report.line(format_args!(
+		"  Readonly Signed Accounts: {}",
+		message.header.num_readonly_signed_accounts
+	));
+	report.line(format_args!(
+		"  Readonly Unsigned Accounts: {}",
+		message.header.num_readonly_unsigned_accounts
+	));
+
+	report.line("Account Keys:");
+	for (index, pubkey) in message.account_keys.iter().enumerate() {
+		let role = match (
+			message.is_signer(index),
+			message.is_maybe_writable(index, None),
+		) {
+			(true, true) => "signer, writable",
+			(true, false) => "signer, readonly",
+			(false, true) => "writable",
+			(false, false) => "readonly",
+		};
+		report.line(format_args!("  [{}] {} ({})", index, pubkey, role));
+	}
+
+	report.line(format_args!(
+		"Recent Blockhash: {}",
+		message.recent_blockhash
+	));
+
+	report.line("Instructions:");
+	for (index, instruction) in message.instructions.iter().enumerate() {
+		report.line(format_args!(
+			"  [{}] Program Index: {}, Account Indices: {:?}, Data Length: {}",
+			index,
+			instruction.program_id_index,
+			instruction.accounts,
+			instruction.data.len()
+		));
+	}
+}
+
+/// Reads the amount held by `token_account`, treating a missing account as empty. Passing
+/// `min_context_slot` ensures the read is not served from state older than that slot.
+fn token_balance(
+	client: &impl RpcProvider,
+	token_account: &Pubkey,
+	min_context_slot: Option<Slot>,
+) -> Result<u64> {
+	let account = client
+		.get_account_with_config(
+			token_account,
+			RpcAccountInfoConfig {
+				encoding: Some(UiAccountEncoding::Base64),
+				min_context_slot,
+				..RpcAccountInfoConfig::default()
+			},
+		)
+		.map_err(|err| anyhow!("Unable to fetch token account {}: {}", token_account, err))?
+		.value;
+
+	match account {
+		Some(account) => Ok(unpack_token_account(&account.data)?.amount),
+		None => Ok(0),
+	}
+}
+
+/// Unpacks a token account of either token program. Token-2022 accounts with extensions are
+/// longer than the legacy layout, which `Pack::unpack` rejects.
+fn unpack_token_account(data: &[u8]) -> Result<Token2022Account, ProgramError> {
+	StateWithExtensions::<Token2022Account>::unpack(data).map(|account| account.base)
+}
+
+/// Returns the mint of `data` if it holds a token account of either token program. Token-2022
+/// accounts with extensions are longer than legacy ones and are tagged after the base layout.
+fn token_account_mint(data: &[u8]) -> Option<Pubkey> {
+	const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+	let is_token_account = data.len() == TokenAccount::LEN
+		|| data.get(TokenAccount::LEN) == Some(&ACCOUNT_TYPE_ACCOUNT);
+
+	if is_token_account {
+		Pubkey::try_from(&data[..32]).ok()
+	} else {
+		None
+	}
+}
+
+/// Returns the token program owning `mint`, falling back to Token-2022 when `token_2022` is set
+/// and the legacy token program otherwise if the mint cannot be read.
+fn mint_token_program(
+	client: &impl RpcProvider,
+	mint: &Pubkey,
+	token_2022: bool,
+) -> Result<Pubkey> {
+	let account = client
+		.get_account_with_commitment(mint, client.commitment())
+		.map_err(|err| anyhow!("Unable to fetch mint {}: {}", mint, err))?
+		.value;
+
+	match account {
+		Some(account)
+			if account.owner == spl_token::id() || account.owner == spl_token_2022::id() =>
+		{
+			Ok(account.owner)
+		}
+		_ if token_2022 => Ok(spl_token_2022::id()),
+		_ => Ok(spl_token::id()),
+	}
+}
+
+/// Builds instructions creating `owner`'s associated token accounts for each of `mints`, given
+/// as `(mint, token program)` pairs, that does not exist yet, paid for by `payer`.
+fn create_missing_token_accounts(
+	client: &impl RpcProvider,
+	payer: &Pubkey,
+	owner: &Pubkey,
+	mints: &[(Pubkey, Pubkey)],
+) -> Result<Vec<Instruction>> {
+	let addresses: Vec<Pubkey> = mints
+		.iter()
+		.map(|(mint, token_program)| {
+			spl_associated_token_account::get_associated_token_address_with_program_id(
+				owner,
+				mint,
+				token_program,
+			)
+		})
+		.collect();
+
+	let accounts = client
+		.get_multiple_accounts(&addresses)
+		.map_err(|err| anyhow!("Unable to fetch token accounts: {}", err))?;
+
+	Ok(mints
+		.iter()
+		.zip(accounts)
+		.filter(|(_, account)| account.is_none())
+		.map(|((mint, token_program), _)| {
+			spl_associated_token_account::instruction::create_associated_token_account(
+				payer,
+				owner,
+				mint,
+				token_program,
+			)
+		})
+		.collect())
+}
+
+/// Ensures every existing token account referenced by `instructions` is owned by the same
+/// token program as its mint, which breaks when an account predates a Token-2022 migration.
+fn check_token_account_programs(
+	client: &impl RpcProvider,
+	instructions: &[Instruction],
+) -> Result<()> {
+	let mut addresses: Vec<Pubkey> = instructions
+		.iter()
+		.flat_map(|instruction| instruction.accounts.iter().map(|account| account.pubkey))
+		.collect();
+	addresses.sort();
+	addresses.dedup();
+
+	let accounts = client
+		.get_multiple_accounts(&addresses)
+		.map_err(|err| anyhow!("Unable to fetch accounts: {}", err))?;
+
+	let token_accounts: Vec<(Pubkey, Pubkey, Pubkey)> = addresses
+		.iter()
+		.zip(accounts)
+		.filter_map(|(address, account)| {
+			let account = account?;
+			if account.owner != spl_token::id() && account.owner != spl_token_2022::id() {
+				return None;
+			}
+
+			Some((*address, account.owner, token_account_mint(&account.data)?))
+		})
+		.collect();
+
+	let mint_addresses: Vec<Pubkey> = token_accounts.iter().map(|(_, _, mint)| *mint).collect();
+	let mint_accounts = client
+		.get_multiple_accounts(&mint_addresses)
+		.map_err(|err| anyhow!("Unable to fetch mint accounts: {}", err))?;
+
+	for ((address, token_program, mint), mint_account) in token_accounts.iter().zip(mint_accounts) {
+		let Some(mint_account) = mint_account else {
+			continue;
+		};
+
+		if mint_account.owner != *token_program {
+			return Err(anyhow!(
+				"Token account {} is owned by {} but its mint {} belongs to {}. Close the \
+				 account and recreate it under the mint's token program",
+				address,
+				token_program,
+				mint,
+				mint_account.owner
+			));
+		}
+	}
+
+	Ok(())
+}
+
+/// Returns the expiry date of the tokenizer at `tokenizer_address` and its RFC 3339 form.
+fn tokenizer_expiry(
+	client: &impl RpcProvider,
+	tokenizer_address: &Pubkey,
+) -> Result<(i64, String)> {
+	let tokenizer = state::fetch_tokenizer(client, tokenizer_address)?;
+
+	let expiry = DateTime::from_timestamp(tokenizer.expiry_date, 0)
+		.map(|expiry| expiry.to_rfc3339())
+		.unwrap_or_else(|| tokenizer.expiry_date.to_string());
+
+	Ok((tokenizer.expiry_date, expiry))
+}
+
+/// Aborts tokenize operations on a tokenizer that has already expired.
+fn ensure_tokenizer_active(
+	client: &impl RpcProvider,
+	tokenizer_address: &Pubkey,
+	now: i64,
+) -> Result<()> {
+	let (expiry_date, expiry) = tokenizer_expiry(client, tokenizer_address)?;
+
+	if expiry_date <= now {
+		return Err(anyhow!(
+			"Tokenizer {} expired at {}. Tokens minted after expiry have no time left to accrue \
+			 yield, so this is almost always a mistake. Pass `--force` to proceed anyway",
+			tokenizer_address,
+			expiry
+		));
+	}
+
+	Ok(())
+}
+
+/// Aborts principal-only redemptions on a tokenizer that has not expired yet, which the program
+/// only allows once the principal has matured.
+fn ensure_tokenizer_expired(
+	client: &impl RpcProvider,
+	tokenizer_address: &Pubkey,
+	now: i64,
+) -> Result<()> {
+	let (expiry_date, expiry) = tokenizer_expiry(client, tokenizer_address)?;
+
+	if expiry_date > now {
+		return Err(anyhow!(
+			"Tokenizer {} does not expire until {}, so principal cannot be redeemed on its own \
+			 yet. Redeem principal and yield together, or pass `--force` to proceed anyway",
+			tokenizer_address,
+			expiry
+		));
+	}
+
+	Ok(())
+}
+
+/// Rejects an expiry date at or before `now`, which would create a market that is dead on
+/// arrival.
+fn ensure_expiry_in_future(expiry_date: i64, now: i64) -> Result<()> {
+	if expiry_date <= now {
+		let expiry = DateTime::from_timestamp(expiry_date, 0)
+			.map(|expiry| expiry.to_rfc3339())
+			.unwrap_or_else(|| expiry_date.to_string());
+
+		return Err(anyhow!(
+			"Expiry {} is not in the future. Pass `--allow-past-expiry` to initialize it anyway",
+			expiry
+		));
+	}
+
+	Ok(())
+}
+
+/// Rejects a tokenizer address that does not derive from the underlying mint and the expiry
+/// date it was initialized with, catching an address copied from the wrong market before any
+/// funds move.
+fn ensure_tokenizer_address(common_fields: &InstructionCommonFields, expiry: i64) -> Result<()> {
+	let (tokenizer_address, _) = get_tokenizer_address(
+		&common_fields.underlying_mint_address(),
+		expiry,
+		&program_id(),
+	);
+
+	if tokenizer_address != common_fields.lysergic_tokenizer_address {
+		return Err(anyhow!(
+			"Tokenizer {} does not match underlying mint {} and expiry {}, which derive \
+			 tokenizer {}",
+			common_fields.lysergic_tokenizer_address,
+			common_fields.underlying_mint_address(),
+			expiry,
+			tokenizer_address
+		));
+	}
+
+	Ok(())
+}
+
+/// Fails when `token_account` holds less than `amount` of underlying, instead of letting the
+/// deposit revert on-chain.
+fn ensure_underlying_balance(
+	client: &impl RpcProvider,
+	token_account: &Pubkey,
+	amount: u64,
+) -> Result<()> {
+	let balance: u64 = client
+		.get_token_account_balance(token_account)
+		.map_err(|err| anyhow!("Unable to fetch balance of {}: {}", token_account, err))?
+		.amount
+		.parse()
+		.map_err(|err| anyhow!("Invalid balance of {}: {}", token_account, err))?;
+
+	if balance < amount {
+		return Err(anyhow!(
+			"Insufficient underlying in {}: have {}, need {}",
+			token_account,
+			balance,
+			amount
+		));
+	}
+
+	Ok(())
+}
+
+/// Fails unless `source` is a token account of `underlying_mint` owned by `authority`, the only
+/// kind of account a deposit can draw from.
+fn ensure_underlying_source(
+	client: &impl RpcProvider,
+	source: &Pubkey,
+	underlying_mint: &Pubkey,
+	authority: &Pubkey,
+) -> Result<()> {
+	let source_account = client
+		.get_account(source)
+		.map_err(|err| anyhow!("Unable to fetch account {}: {}", source, err))?;
+
+	let source_token_account = unpack_token_account(&source_account.data)
+		.map_err(|err| anyhow!("{} is not a token account: {}", source, err))?;
+
+	if source_token_account.mint != *underlying_mint {
+		return Err(anyhow!(
+			"{} holds mint {}, expected underlying mint {}",
+			source,
+			source_token_account.mint,
+			underlying_mint
+		));
+	}
+
+	if source_token_account.owner != *authority {
+		return Err(anyhow!(
+			"{} is owned by {}, not the signer {}",
+			source,
+			source_token_account.owner,
+			authority
+		));
+	}
+
+	Ok(())
+}
+
+/// Warns when the vault cannot cover a principal redemption of `amount`, which would
+/// otherwise only surface as an opaque on-chain failure.
+fn check_vault_liquidity(
+	client: &impl RpcProvider,
+	tokenizer: &Pubkey,
+	vault: &Pubkey,
+	amount: u64,
+) -> Result<()> {
+	let tokenizer_account = client
+		.get_account_with_commitment(tokenizer, client.commitment())
+		.map_err(|err| anyhow!("Unable to fetch tokenizer {}: {}", tokenizer, err))?
+		.value;
+
+	match tokenizer_account {
+		None => eprintln!(
+			"Warning: tokenizer {} does not exist, the redemption will fail",
+			tokenizer
+		),
+		Some(account) if account.owner != program_id() => eprintln!(
+			"Warning: {} is not owned by the tokenizer program, the redemption will fail",
+			tokenizer
+		),
+		Some(_) => {}
+	}
+
+	let vault_balance = token_balance(client, vault, None)?;
+	if vault_balance < amount {
+		eprintln!(
+			"Warning: vault {} holds {} underlying, not enough to redeem {} principal",
+			vault, vault_balance, amount
+		);
+	}
+
+	Ok(())
+}
+
+fn landed_slot(client: &impl RpcProvider, signature: &Signature) -> Result<Option<Slot>> {
+	Ok(client
+		.get_signature_statuses(&[*signature])
+		.map_err(|err| anyhow!("Unable to get status of {}: {}", signature, err))?
+		.value
+		.into_iter()
+		.flatten()
+		.next()
+		.map(|status| status.slot))
+}
+
+/// Prints, for each writable account of `message`, the slot the transaction landed in and the
+/// slot of a follow-up read that is guaranteed to include the transaction's writes.
+fn print_account_slots(
+	client: &impl RpcProvider,
+	message: &Message,
+	signature: &Signature,
+	report: &mut output::Report,
+) -> Result<()> {
+	let landed_slot = landed_slot(client, signature)?
+		.ok_or_else(|| anyhow!("Unable to find the slot {} landed in", signature))?;
+
+	report.field("Landed Slot", landed_slot);
+
+	let mut account_slots = Vec::new();
+	for (index, pubkey) in message.account_keys.iter().enumerate() {
+		if !message.is_maybe_writable(index, None) {
+			continue;
+		}
+
+		let observed_slot = client
+			.get_account_with_config(
+				pubkey,
+				RpcAccountInfoConfig {
+					encoding: Some(UiAccountEncoding::Base64),
+					min_context_slot: Some(landed_slot),
+					..RpcAccountInfoConfig::default()
+				},
+			)
+			.map_err(|err| anyhow!("Unable to fetch account {}: {}", pubkey, err))?
+			.context
+			.slot;
+
+		account_slots.push(format!(
+			"{} (updated at slot {}, observed at slot {})",
+			pubkey, landed_slot, observed_slot
+		));
+	}
+	report.list("Account Slots", account_slots);
+
+	Ok(())
+}
+
+/// Polls until `signature` reaches `commitment`, returning the slot the transaction landed in.
+fn wait_for_commitment(
+	client: &impl RpcProvider,
+	signature: &Signature,
+	commitment: CommitmentConfig,
+) -> Result<Slot, CliError> {
+	const MAX_POLLS: usize = 120;
+	const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+	for _ in 0..MAX_POLLS {
+		let status = client
+			.get_signature_statuses(&[*signature])?
+			.value
+			.into_iter()
+			.flatten()
+			.next();
+
+		if let Some(status) = status.filter(|status| status.satisfies_commitment(commitment)) {
+			return match status.err {
+				Some(err) => Err(CliError::TransactionFailed(err.to_string())),
+				None => Ok(status.slot),
+			};
+		}
+
+		thread::sleep(POLL_INTERVAL);
+	}
+
+	Err(CliError::ConfirmFailed(
+		*signature,
+		format!(
+			"did not reach `{:?}` commitment in time",
+			commitment.commitment
+		),
+	))
+}
+
+/// Sends `transaction` without preflight simulation and polls until it reaches the client's
+/// commitment.
+fn send_and_confirm_without_preflight(
+	client: &impl RpcProvider,
+	transaction: &Transaction,
+) -> ClientResult<Signature> {
+	let signature = client.send_transaction_with_config(
+		transaction,
+		RpcSendTransactionConfig {
+			skip_preflight: true,
+			..RpcSendTransactionConfig::default()
+		},
+	)?;
+
+	client.poll_for_signature(&signature)?;
+
+	Ok(signature)
+}
+
+/// Turns a failed send into an error naming the tokenizer program error, if one was raised.
+fn send_error(err: ClientError, message: &Message) -> CliError {
+	match err.get_transaction_error() {
+		Some(transaction_error) => CliError::TransactionFailed(decode::describe_transaction_error(
+			&transaction_error,
+			message,
+		)),
+		None => CliError::SendFailed(err),
+	}
+}
+
+/// Whether a send failed because the transaction's blockhash expired, which re-signing with a
+/// fresh blockhash fixes.
+fn is_blockhash_expired(err: &ClientError) -> bool {
+	match err.kind() {
+		ClientErrorKind::TransactionError(TransactionError::BlockhashNotFound) => true,
+		ClientErrorKind::RpcError(RpcError::RpcResponseError {
+			data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+			..
+		}) => result.err == Some(TransactionError::BlockhashNotFound),
+		ClientErrorKind::RpcError(
+			RpcError::ForUser(message) | RpcError::RpcResponseError { message, .. },
+		) => message.contains("Blockhash not found"),
+		_ => false,
+	}
+}
+
+/// Whether a send failed in transport and may succeed when simply retried, as opposed to the
+/// transaction itself being rejected by the cluster or the program.
+fn is_transient(err: &ClientError) -> bool {
+	matches!(
+		err.kind(),
+		ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_)
+	)
+}
+
+/// Whether the RPC node refused a request for exceeding its rate limit, which it answers with
+/// HTTP 429 or, behind some providers, a JSON-RPC error of the same code.
+fn is_rate_limited(err: &ClientError) -> bool {
+	const TOO_MANY_REQUESTS: u16 = 429;
+
+	match err.kind() {
+		ClientErrorKind::Reqwest(err) => {
+			err.status().map(|status| status.as_u16()) == Some(TOO_MANY_REQUESTS)
+		}
+		ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+			*code == i64::from(TOO_MANY_REQUESTS)
+		}
+		_ => false,
+	}
+}
+
+/// Fails when `amount` exceeds the cap on a single operation on `underlying_mint`, which is
+/// `max_operation_amount` when given and the env profile's cap for the mint otherwise.
+fn ensure_within_operation_cap(
+	max_operation_amount: Option<u64>,
+	env_profile: &config::EnvProfile,
+	underlying_mint: &Pubkey,
+	amount: u64,
+) -> Result<()> {
+	match max_operation_amount.or_else(|| env_profile.max_operation_amount(underlying_mint)) {
+		Some(max_operation_amount) if amount > max_operation_amount => Err(anyhow!(
+			"Amount {} exceeds the maximum operation amount of {}",
+			amount,
+			max_operation_amount
+		)),
+		_ => Ok(()),
+	}
+}
+
+/// Returns the fee of `message`, which must carry a recent blockhash, failing when it exceeds
+/// `max_fee`.
+fn ensure_fee_within(
+	client: &impl RpcProvider,
+	message: &Message,
+	max_fee: Option<u64>,
+) -> Result<u64> {
+	let fee = client
+		.get_fee_for_message(message)
+		.map_err(|err| anyhow!("Unable to get fee for message: {}", err))?;
+	debug!("Fee: {} lamports", fee);
+
+	match max_fee {
+		Some(max_fee) if fee > max_fee => Err(anyhow!(
+			"Fee of {} lamports exceeds `--max-fee` of {} lamports",
+			fee,
+			max_fee
+		)),
+		_ => Ok(fee),
+	}
+}
+
+/// Returns how long to wait before the `retry`th retry of a send, counting from 1. The backoff
+/// doubles with each retry and is fully jittered, drawn uniformly from zero up to the backoff,
+/// so concurrent runs sharing an RPC do not retry in lockstep.
+fn backoff_delay(retry: u32, rng: &mut impl Rng) -> Duration {
+	const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+	let max_backoff = INITIAL_BACKOFF.saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)));
+	rng.gen_range(Duration::ZERO..=max_backoff)
+}
+
+/// Sends and confirms `transaction`. Transient failures are retried up to `max_retries` times
+/// after a `backoff_delay`. When `refresh_blockhash` is set, an expired blockhash is replaced
+/// and the transaction re-signed until it has been signed `max_sign_attempts` times. Returns
+/// the signature and the sends made.
+fn send_with_retries(
+	client: &impl RpcProvider,
+	transaction: &mut Transaction,
+	signers: &[&dyn Signer],
+	max_retries: u32,
+	max_sign_attempts: u32,
+	skip_preflight: bool,
+	refresh_blockhash: bool,
+) -> Result<(Signature, u32)> {
+	let mut attempts = 0;
+	let mut retries = 0;
+	let mut sign_attempts = 1;
+	loop {
+		attempts += 1;
+
+		let result = if skip_preflight {
+			send_and_confirm_without_preflight(client, transaction)
+		} else {
+			client.send_and_confirm_transaction(transaction)
+		};
+
+		match result {
+			Ok(signature) => return Ok((signature, attempts)),
+			Err(err)
+				if refresh_blockhash
+					&& sign_attempts < max_sign_attempts
+					&& is_blockhash_expired(&err) =>
+			{
+				sign_attempts += 1;
+				eprintln!(
+					"Blockhash expired, re-signing with a fresh blockhash (sign attempt {} of {})",
+					sign_attempts, max_sign_attempts
+				);
+
+				let blockhash = client.get_latest_blockhash().map_err(CliError::RpcError)?;
+				transaction
+					.try_sign(signers, blockhash)
+					.map_err(|err| anyhow!("Unable to sign transaction: {}", err))?;
+			}
+			Err(err) if retries < max_retries && is_transient(&err) => {
+				retries += 1;
+				let backoff = backoff_delay(retries, &mut rand::thread_rng());
+				eprintln!(
+					"Attempt {} failed ({}), retrying in {}ms",
+					attempts,
+					err,
+					backoff.as_millis()
+				);
+				thread::sleep(backoff);
+			}
+			Err(err) => return Err(send_error(err, &transaction.message).into()),
+		}
+	}
+}
+
+/// Sends `transaction` without awaiting confirmation, retrying transient failures up to
+/// `max_retries` times after a `backoff_delay`.
+fn send_transaction_with_retries(
+	client: &impl RpcProvider,
+	transaction: &Transaction,
+	max_retries: u32,
+	skip_preflight: bool,
+) -> Result<Signature> {
+	let config = RpcSendTransactionConfig {
+		skip_preflight,
+		..RpcSendTransactionConfig::default()
+	};
+
+	let mut retries = 0;
+	loop {
+		match client.send_transaction_with_config(transaction, config) {
+			Ok(signature) => return Ok(signature),
+			Err(err) if retries < max_retries && is_transient(&err) => {
+				retries += 1;
+				let backoff = backoff_delay(retries, &mut rand::thread_rng());
+				eprintln!(
+					"Send failed ({}), retrying in {}ms",
+					err,
+					backoff.as_millis()
+				);
+				thread::sleep(backoff);
+			}
+			Err(err) => return Err(send_error(err, &transaction.message).into()),
+		}
+	}
+}
+
+/// Sends `transaction` and awaits its confirmation through a websocket signature
+/// subscription, falling back to polling when the websocket is unavailable or closes early.
+fn send_and_confirm_with_websocket(
+	client: &impl RpcProvider,
+	ws_url: &str,
+	transaction: &Transaction,
+	max_retries: u32,
+	skip_preflight: bool,
+) -> Result<Signature> {
+	const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+	let signature = transaction.signatures[0];
+	let config = RpcSignatureSubscribeConfig {
+		commitment: Some(client.commitment()),
+		enable_received_notification: Some(false),
+	};
+
+	let (mut subscription, receiver) =
+		match PubsubClient::signature_subscribe(ws_url, &signature, Some(config)) {
+			Ok(signature_subscription) => signature_subscription,
+			Err(err) => {
+				eprintln!("Websocket unavailable ({}), falling back to polling", err);
+				send_transaction_with_retries(client, transaction, max_retries, skip_preflight)?;
+				client
+					.poll_for_signature(&signature)
+					.map_err(|err| send_error(err, &transaction.message))?;
+				return Ok(signature);
+			}
+		};
+
+	send_transaction_with_retries(client, transaction, max_retries, skip_preflight)?;
+
+	let notification = receiver.recv_timeout(CONFIRMATION_TIMEOUT);
+	let _ = subscription.shutdown();
+
+	match notification {
+		Ok(response) => {
+			match response.value {
+				RpcSignatureResult::ProcessedSignature(result) => match result.err {
+					Some(err) => Err(CliError::TransactionFailed(
+						decode::describe_transaction_error(&err, &transaction.message),
+					)
+					.into()),
+					None => Ok(signature),
+				},
+				RpcSignatureResult::ReceivedSignature(_) => Err(CliError::ConfirmFailed(
+					signature,
+					"unexpected received notification".to_string(),
+				)
+				.into()),
+			}
+		}
+		Err(RecvTimeoutError::Timeout) => Err(CliError::ConfirmFailed(
+			signature,
+			format!(
+				"no confirmation received over the websocket within {}s",
+				CONFIRMATION_TIMEOUT.as_secs()
+			),
+		)
+		.into()),
+		Err(RecvTimeoutError::Disconnected) => {
+			eprintln!("Websocket closed before confirmation, falling back to polling");
+			client
+				.confirm_transaction_with_spinner(
+					&signature,
+					&transaction.message.recent_blockhash,
+					client.commitment(),
 				)
-				.map_err(|err| anyhow!("Unable to create `Terminate` instruction: {}", err))?
+				.map_err(|err| CliError::ConfirmFailed(signature, err.to_string()))?;
+			Ok(signature)
+		}
+	}
+}
+
+/// Submits `transaction` to every endpoint in `urls` in parallel and returns as soon as any
+/// of them confirms it, along with the endpoint that did. Each endpoint gets a client with the
+/// same client id, timeout and preflight setting as the main one. The remaining submissions are
+/// left to finish on their own.
+fn broadcast_and_confirm(
+	urls: Vec<String>,
+	commitment: CommitmentConfig,
+	options: &TransactionOptions,
+	transaction: &Transaction,
+) -> Result<(Signature, String)> {
+	let (sender, receiver) = mpsc::channel();
+
+	for url in urls {
+		let sender = sender.clone();
+		let transaction = transaction.clone();
+		let client_id = options.client_id.clone();
+		let rpc_timeout = options.rpc_timeout;
+		let skip_preflight = options.skip_preflight;
+
+		thread::spawn(move || {
+			let result = new_rpc_client(
+				vec![url.clone()],
+				commitment,
+				client_id.as_deref(),
+				rpc_timeout,
+			)
+			.and_then(|client| {
+				if skip_preflight {
+					send_and_confirm_without_preflight(&client, &transaction)
+				} else {
+					client.send_and_confirm_transaction(&transaction)
+				}
+				.map_err(anyhow::Error::from)
+			});
+			let _ = sender.send((url, result));
+		});
+	}
+	drop(sender);
+
+	let mut errors = Vec::new();
+	for (url, result) in receiver {
+		match result {
+			Ok(signature) => return Ok((signature, url)),
+			Err(err) => errors.push(format!("  {}: {}", url, err)),
+		}
+	}
+
+	Err(CliError::BroadcastFailed(errors.join("\n")).into())
+}
+
+/// Shows a spinner on stderr while the transaction confirms, cleared once it is dropped. Json
+/// output and piped stderr get a single status line instead.
+fn confirmation_spinner(json: bool) -> Option<ProgressBar> {
+	if json || !io::stderr().is_terminal() {
+		eprintln!("Awaiting confirmation");
+		return None;
+	}
+
+	let spinner = ProgressBar::new_spinner().with_finish(ProgressFinish::AndClear);
+	spinner.set_message("Awaiting confirmation");
+	spinner.enable_steady_tick(Duration::from_millis(100));
+
+	Some(spinner)
+}
+
+/// Asks the user to confirm on stdin. Piped stdin cannot answer, so it aborts instead of
+/// waiting for input.
+fn confirm(prompt: &str) -> Result<bool> {
+	if !io::stdin().is_terminal() {
+		return Err(anyhow!(
+			"{} Refusing to prompt on non-interactive stdin, pass `--yes` to proceed",
+			prompt
+		));
+	}
+
+	print!("{} [y/N] ", prompt);
+	io::stdout().flush()?;
+
+	let mut answer = String::new();
+	io::stdin().read_line(&mut answer)?;
+
+	Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Starts the report of `command`, in the requested output format and recorded to the
+/// `--output-file` ledger.
+fn new_report(args: &Cli, command: &str) -> output::Report {
+	output::Report::new(args.output)
+		.ledger(args.output_file.as_deref(), command)
+		.select(&args.output_fields)
+}
+
+fn manage_aliases(command: &Aliases, report: &mut output::Report) -> Result<()> {
+	let mut aliases = config::load_aliases()?;
+
+	match command {
+		Aliases::Add(alias_fields) => {
+			aliases.insert(alias_fields.name.clone(), alias_fields.address.to_string());
+			config::save_aliases(&aliases)
+		}
+		Aliases::List => {
+			report.json_field("Aliases", &aliases)?;
+			if report.is_json() {
+				return Ok(());
+			}
+
+			for (name, address) in &aliases {
+				println!("{}: {}", name, address);
+			}
+			Ok(())
+		}
+		Aliases::Remove(alias_fields) => {
+			if aliases.remove(&alias_fields.name).is_none() {
+				return Err(anyhow!("Unknown alias `{}`", alias_fields.name));
+			}
+			config::save_aliases(&aliases)
+		}
+	}
+}
+
+/// Submits a signed base64 transaction, refusing one that is missing a required signature. It
+/// cannot be re-signed, so it is resent unchanged should its blockhash expire.
+fn submit(
+	client: &impl RpcProvider,
+	options: &TransactionOptions,
+	transaction_base64: &str,
+	report: &mut output::Report,
+) -> Result<()> {
+	let mut transaction = decode::deserialize_transaction(transaction_base64)?;
+
+	let missing_signers: Vec<String> = transaction
+		.message
+		.signer_keys()
+		.into_iter()
+		.zip(&transaction.signatures)
+		.filter(|(_, signature)| **signature == Signature::default())
+		.map(|(signer, _)| signer.to_string())
+		.collect();
+
+	if !missing_signers.is_empty() {
+		return Err(anyhow!(
+			"Transaction is missing signatures from: {}",
+			missing_signers.join(", ")
+		));
+	}
+
+	transaction
+		.verify()
+		.map_err(|err| anyhow!("Transaction has an invalid signature: {}", err))?;
+
+	options.send(client, &mut transaction, &[], false, report)?;
+
+	Ok(())
+}
+
+/// Rebuilds the tokenizer instructions of the transaction `signature` and, once confirmed, sends
+/// them again through the same path as every other command.
+fn replay(
+	client: &impl RpcProvider,
+	options: &TransactionOptions,
+	wallet_keypair: &dyn Signer,
+	signature: &Signature,
+	yes: bool,
+	report: &mut output::Report,
+) -> Result<()> {
+	let confirmed_transaction = client
+		.get_transaction(signature, UiTransactionEncoding::Base64)
+		.map_err(|err| anyhow!("Unable to fetch transaction {}: {}", signature, err))?;
+
+	let versioned_transaction = confirmed_transaction
+		.transaction
+		.transaction
+		.decode()
+		.ok_or_else(|| anyhow!("Unable to decode transaction {}", signature))?;
+
+	let message = match versioned_transaction.message {
+		VersionedMessage::Legacy(message) => message,
+		VersionedMessage::V0(_) => return Err(anyhow!("Only legacy transactions can be replayed")),
+	};
+
+	let mut instructions: Vec<Instruction> = decode::decompile_message(&message)?
+		.into_iter()
+		.filter(|instruction| instruction.program_id == program_id())
+		.collect();
+
+	if instructions.is_empty() {
+		return Err(anyhow!(
+			"Transaction {} does not contain any tokenizer instructions",
+			signature
+		));
+	}
+
+	let wallet_pubkey = wallet_keypair.pubkey();
+	for instruction in &instructions {
+		let decoded_instruction = decode::decode_instruction(&instruction.data)?;
+		report.line(format_args!("Instruction: {:?}", decoded_instruction));
+
+		for account in &instruction.accounts {
+			report.line(format_args!(
+				"  {} (signer: {}, writable: {})",
+				account.pubkey, account.is_signer, account.is_writable
+			));
+		}
+
+		if let Some(account) = instruction
+			.accounts
+			.iter()
+			.find(|account| account.is_signer && account.pubkey != wallet_pubkey)
+		{
+			return Err(anyhow!(
+				"Replay requires a signature from {}, which is not the current wallet",
+				account.pubkey
+			));
+		}
+	}
+
+	if !yes && !confirm("Re-send this transaction?")? {
+		return Ok(());
+	}
+
+	report.field("Replayed Signature", signature);
+
+	options.wrap(&wallet_pubkey, &mut instructions);
+	report.instructions(&instructions);
+
+	let mut transaction = Transaction::new_with_payer(&instructions, Some(&wallet_pubkey));
+	let latest_blockhash = client
+		.get_latest_blockhash()
+		.map_err(|err| anyhow!("Unable to get latest blockhash: {}", err))?;
+
+	transaction
+		.try_sign(&[wallet_keypair], latest_blockhash)
+		.map_err(|err| anyhow!("Unable to sign transaction: {}", err))?;
+	options.send(client, &mut transaction, &[wallet_keypair], true, report)?;
+
+	Ok(())
+}
+
+/// Reports the tokenizer, vault and mint addresses for an underlying mint and expiry.
+fn derive(
+	common_fields: &InitializeCommonFields,
+	now: i64,
+	underlying_token_program: &Pubkey,
+	trace: bool,
+	report: &mut output::Report,
+) -> Result<()> {
+	let expiry = Expiry::from_i64(common_fields.expiry()?)
+		.map_err(|err| anyhow!("Unable to parse the given value to `Expiry`: {}", err))?;
+
+	let expiry_date = expiry
+		.to_expiry_date(now)
+		.expect("Unable to convert expiry to expiry date");
+
+	let (lysergic_tokenizer_address, _) = get_tokenizer_address(
+		&common_fields.underlying_mint_address,
+		expiry_date,
+		&program_id(),
+	);
+
+	if trace {
+		trace_derivations(
+			&common_fields.underlying_mint_address,
+			expiry_date,
+			underlying_token_program,
+			report,
+		);
+	}
+
+	let underlying_vault_address =
+		spl_associated_token_account::get_associated_token_address_with_program_id(
+			&lysergic_tokenizer_address,
+			&common_fields.underlying_mint_address,
+			underlying_token_program,
+		);
+
+	let (principal_mint_address, _) =
+		get_principal_mint_address(&lysergic_tokenizer_address, &program_id());
+
+	let (yield_mint_address, _) =
+		get_yield_mint_address(&lysergic_tokenizer_address, &program_id());
+
+	report.field("Tokenizer Address", lysergic_tokenizer_address);
+	report.field("Vault Address", underlying_vault_address);
+	report.field("Principal Mint Address", principal_mint_address);
+	report.field("Yield Mint Address", yield_mint_address);
+
+	Ok(())
+}
+
+/// Returns the pubkey of the configured payer, which may be a bare pubkey, for commands that
+/// only read the wallet's accounts.
+fn payer_pubkey(path: &str) -> Result<Pubkey> {
+	if let Ok(pubkey) = Pubkey::from_str(path) {
+		return Ok(pubkey);
+	}
+
+	read_payer_signer(path)
+		.map(|signer| signer.pubkey())
+		.map_err(|err| anyhow!("Unable to load payer {}: {}", path, err))
+}
+
+/// Returns the subcommand path of `matches`, such as `tokenize principal-yield`.
+fn command_name(matches: &ArgMatches) -> String {
+	let mut names = Vec::new();
+	let mut subcommand = matches.subcommand();
+	while let Some((name, sub_matches)) = subcommand {
+		names.push(name);
+		subcommand = sub_matches.subcommand();
+	}
+
+	names.join(" ")
+}
+
+/// `--success-on accepted` returns as soon as the node accepts the transaction, so options that
+/// act on the confirmed transaction cannot be combined with it. Clap cannot express a conflict
+/// with one value of an argument that has a default, hence the check after parsing.
+fn check_success_on(args: &Cli) -> Result<(), clap::Error> {
+	if args.success_on != SuccessOn::Accepted {
+		return Ok(());
+	}
+
+	let conflicts = [
+		("--broadcast-to", !args.broadcast_to.is_empty()),
+		("--show-delta", args.show_delta),
+		("--report-account-slots", args.report_account_slots),
+		(
+			"--confirm-with-commitment-escalation",
+			args.confirm_with_commitment_escalation,
+		),
+	];
+	match conflicts.iter().find(|(_, set)| *set) {
+		Some((flag, _)) => Err(Cli::command().error(
+			ErrorKind::ArgumentConflict,
+			format!(
+				"the argument '--success-on accepted' cannot be used with '{}'",
+				flag
+			),
+		)),
+		None => Ok(()),
+	}
+}
+
+fn main() -> Result<()> {
+	let matches = Cli::command().get_matches();
+	let args = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+	check_success_on(&args).unwrap_or_else(|err| err.exit());
+	let output = args.output;
+
+	let result = run(args, &command_name(&matches));
+	if let (Err(ref err), output::OutputFormat::Json) = (&result, output) {
+		output::print_error(err)?;
+		std::process::exit(1);
+	}
+
+	result
+}
+
+fn run(mut args: Cli, command: &str) -> Result<()> {
+	if args.verbose {
+		// Dependencies log every RPC request at debug level, which drowns out the CLI's own output
+		env_logger::Builder::new()
+			.filter_level(LevelFilter::Warn)
+			.filter_module(env!("CARGO_CRATE_NAME"), LevelFilter::Debug)
+			.init();
+	}
+	args.cmd = args.cmd.expand_shortcuts();
+
+	let env_profile = match args.env_profile {
+		Some(ref name) => config::load_env_profile(name)?,
+		None => config::EnvProfile::default(),
+	};
+
+	if let Some(program_id) = args.program_id.or(env_profile.program_id) {
+		PROGRAM_ID
+			.set(program_id)
+			.expect("program id is only set once");
+	}
+
+	if let Commands::Aliases(ref aliases) = args.cmd {
+		let mut report = new_report(&args, command);
+		manage_aliases(aliases, &mut report)?;
+		return report.finish();
+	}
+
+	if let Commands::DecodeTx(ref decode_tx_fields) = args.cmd {
+		let mut report = new_report(&args, command);
+		decode::print_transaction(
+			&decode::deserialize_transaction(&decode_tx_fields.transaction)?,
+			&mut report,
+		)?;
+		return report.finish();
+	}
+
+	let solana_config_file = if args.no_default_config {
+		let keypair_path = args
+			.payer
+			.clone()
+			.or_else(|| env_profile.keypair_path.clone())
+			.ok_or_else(|| anyhow!("`--payer` is required when `--no-default-config` is set"))?;
+
+		solana_cli_config::Config {
+			keypair_path,
+			..solana_cli_config::Config::default()
+		}
+	} else {
+		let mut solana_config_file = if let Some(ref config) = args.config {
+			solana_cli_config::Config::load(config)
+				.map_err(|err| anyhow!("Unable to load config file {}: {}", config, err))?
+		} else if let Some(ref config) = *solana_cli_config::CONFIG_FILE {
+			solana_cli_config::Config::load(config).unwrap_or_default()
+		} else {
+			solana_cli_config::Config::default()
+		};
+
+		if let Some(keypair_path) = args.payer.as_ref().or(env_profile.keypair_path.as_ref()) {
+			solana_config_file.keypair_path = keypair_path.clone();
+		}
+
+		solana_config_file
+	};
+
+	let commitment = match args.commitment.as_ref().or(env_profile.commitment.as_ref()) {
+		Some(commitment) => CommitmentConfig::from_str(commitment)
+			.map_err(|err| anyhow!("Invalid commitment `{}`: {}", commitment, err))?,
+		None => CommitmentConfig::confirmed(),
+	};
+
+	// Generated once so the clients `--broadcast-to` builds later send the same id
+	args.client_id = args
+		.client_id
+		.take()
+		.map(|client_id| Some(client_id.unwrap_or_else(|| Uuid::new_v4().to_string())));
+	let client_id = args.client_id.clone().flatten();
+	if let Some(ref client_id) = client_id {
+		eprintln!("Client ID: {}", client_id);
+	}
+
+	// Without the default config there is no config file URL to fall back on
+	let rpc_urls = resolve_rpc_urls(
+		&args.rpc,
+		env_profile.rpc_url.as_deref(),
+		(!args.no_default_config).then_some(solana_config_file.json_rpc_url.as_str()),
+	)
+	.ok_or_else(|| anyhow!("`--rpc` is required when `--no-default-config` is set"))?;
+
+	let client = new_rpc_client(
+		rpc_urls,
+		commitment,
+		client_id.as_deref(),
+		args.rpc_timeout.map(Duration::from_secs),
+	)?;
+	debug!("RPC URL: {}", client.url());
+
+	let time_source = match args.cluster_time_source {
+		Some(ref time_source) => time_source.clone(),
+		None if args.offline => TimeSource::Local,
+		None => TimeSource::default(),
+	};
+
+	execute(
+		args,
+		command,
+		&env_profile,
+		&solana_config_file.keypair_path,
+		&client,
+		&time_source,
+	)
+}
+
+/// Resolves the RPC endpoints to try in order. The first `--rpc` wins over the environment
+/// profile's URL, which wins over the config file's, and any further `--rpc` values follow as
+/// fallbacks. Returns `None` when nothing gives a URL.
+fn resolve_rpc_urls(
+	rpc: &[String],
+	profile_rpc_url: Option<&str>,
+	config_rpc_url: Option<&str>,
+) -> Option<Vec<String>> {
+	let rpc_url = rpc
+		.first()
+		.map(String::as_str)
+		.or(profile_rpc_url)
+		.or(config_rpc_url)?;
+
+	let mut rpc_urls = vec![rpc_url.to_string()];
+	rpc_urls.extend(rpc.iter().skip(1).cloned());
+	Some(rpc_urls)
+}
+
+/// Runs the parsed command against `client`, split from `run` so it can be exercised against an
+/// RPC other than a live cluster.
+fn execute(
+	mut args: Cli,
+	command: &str,
+	env_profile: &config::EnvProfile,
+	keypair_path: &str,
+	client: &(impl RpcProvider + Sync),
+	time_source: &TimeSource,
+) -> Result<()> {
+	if let Commands::Watch(ref watch_fields) = args.cmd {
+		return state::watch(
+			client,
+			&watch_fields.lysergic_tokenizer_address,
+			Duration::from_secs(watch_fields.interval),
+			watch_fields.max_in_flight,
+			time_source,
+			|| new_report(&args, command),
+		);
+	}
+
+	if let Commands::ListMarkets(ref list_markets_fields) = args.cmd {
+		let mut report = new_report(&args, command);
+		state::list_markets(
+			client,
+			list_markets_fields.underlying_mint_address.as_ref(),
+			list_markets_fields.offset,
+			list_markets_fields.limit,
+			&mut report,
+		)?;
+		return report.finish();
+	}
+
+	if let Commands::Submit(ref submit_fields) = args.cmd {
+		let mut report = new_report(&args, command);
+		submit(
+			client,
+			&TransactionOptions::new(&args),
+			&submit_fields.transaction,
+			&mut report,
+		)?;
+		return report.finish();
+	}
+
+	let redeemed_mint = args.cmd.redeemed_mint();
+	if let Some(common_fields) = args.cmd.instruction_fields_mut() {
+		if let Some(underlying_mint) = common_fields.underlying_mint_positional.take() {
+			eprintln!(
+				"Warning: passing the underlying mint positionally is deprecated, use \
+				 `--underlying-mint`"
+			);
+			common_fields.underlying_mint_address = Some(underlying_mint);
+		}
+
+		if common_fields.underlying_mint_address.is_none() {
+			if args.offline {
+				return Err(anyhow!(
+					"`--underlying-mint` is required with `--offline`, the tokenizer account \
+					 cannot be read"
+				));
+			}
+
+			common_fields.underlying_mint_address = Some(state::fetch_underlying_mint(
+				client,
+				&common_fields.lysergic_tokenizer_address,
+			)?);
+		}
+
+		if let Some(ref ui_amount) = common_fields.ui_amount {
+			if args.offline {
+				return Err(anyhow!(
+					"`--ui-amount` cannot be used with `--offline`, the mint's decimals cannot be \
+					 read. Pass the amount in base units instead"
+				));
+			}
+
+			let amount_mint =
+				redeemed_mint.unwrap_or_else(|| common_fields.underlying_mint_address());
+			let decimals = client
+				.get_token_supply(&amount_mint)
+				.map_err(|err| {
+					anyhow!("Unable to fetch decimals of mint {}: {}", amount_mint, err)
+				})?
+				.decimals;
+
+			common_fields.amount = Some(parse_ui_amount(ui_amount, decimals)?);
+		}
+	}
+
+	if let Some(common_fields) = args.cmd.instruction_fields() {
+		let tokenizer_address = &common_fields.lysergic_tokenizer_address;
+		debug!(
+			"Principal mint: {}",
+			get_principal_mint_address(tokenizer_address, &program_id()).0
+		);
+		debug!(
+			"Yield mint: {}",
+			get_yield_mint_address(tokenizer_address, &program_id()).0
+		);
+
+		if common_fields.amount() == 0 {
+			return Err(anyhow!("Amount must be greater than zero"));
+		}
+
+		ensure_within_operation_cap(
+			args.max_operation_amount,
+			env_profile,
+			&common_fields.underlying_mint_address(),
+			common_fields.amount(),
+		)?;
+	}
+
+	let timestamp = time_source.now(client)?;
+
+	if let Some(common_fields) = args.cmd.instruction_fields() {
+		if let Some(expiry) = common_fields.expiry {
+			ensure_tokenizer_address(common_fields, expiry)?;
+		}
+	}
+
+	// The vault and the signer's underlying account live under the underlying mint's program
+	let underlying_token_program = match args.cmd.underlying_mint_address() {
+		Some(ref underlying_mint) if !args.offline => {
+			mint_token_program(client, underlying_mint, args.token_2022)?
+		}
+		_ if args.token_2022 => spl_token_2022::id(),
+		_ => spl_token::id(),
+	};
+	debug!("Underlying token program: {}", underlying_token_program);
+
+	match args.cmd {
+		Commands::Derive(ref common_fields) => {
+			let mut report = new_report(&args, command);
+			derive(
+				common_fields,
+				timestamp,
+				&underlying_token_program,
+				args.trace_derivations,
+				&mut report,
+			)?;
+			return report.finish();
+		}
+		Commands::Status(ref status_fields) => {
+			let mut report = new_report(&args, command);
+			state::status(
+				client,
+				&status_fields.lysergic_tokenizer_address,
+				&mut report,
+			)?;
+			return report.finish();
+		}
+		Commands::Balances(ref balances_fields) => {
+			let owner = match balances_fields.owner {
+				Some(owner) => owner,
+				None => payer_pubkey(keypair_path)?,
+			};
+
+			let mut report = new_report(&args, command);
+			positions::balances(
+				client,
+				&owner,
+				&balances_fields.lysergic_tokenizer_address,
+				&mut report,
+			)?;
+			return report.finish();
+		}
+		Commands::Positions(ref positions_fields) => {
+			let owner = match positions_fields.owner {
+				Some(owner) => owner,
+				None => payer_pubkey(keypair_path)?,
+			};
+
+			let mut report = new_report(&args, command);
+			positions::positions(client, &owner, &mut report)?;
+			return report.finish();
+		}
+		_ => {}
+	}
+
+	if keypair_path == "-" && args.fee_payer.as_deref() == Some("-") {
+		return Err(anyhow!(
+			"`--payer` and `--fee-payer` cannot both read a keypair from stdin"
+		));
+	}
+
+	let wallet_keypair = match read_payer_signer(keypair_path) {
+		Ok(signer) => Some(signer),
+		Err(_) if args.output_signers_needed || args.offline => None,
+		Err(err) => return Err(anyhow!("Unable to load payer {}: {}", keypair_path, err)),
+	};
+
+	// Listing the signers needed and offline building never sign, so the wallet may be given as
+	// a bare pubkey
+	let wallet_pubkey = match wallet_keypair {
+		Some(ref keypair) => keypair.pubkey(),
+		None => args
+			.payer
+			.as_deref()
+			.and_then(|payer| Pubkey::from_str(payer).ok())
+			.ok_or_else(|| {
+				anyhow!("Unable to read keypair file, pass `--payer <PUBKEY>` to build unsigned")
+			})?,
+	};
+
+	let fee_payer_keypair = match args.fee_payer {
+		Some(ref fee_payer) => Some(read_keypair_source(fee_payer).map_err(|err| {
+			anyhow!(
+				"Unable to read fee payer keypair file {}: {}",
+				fee_payer,
+				err
+			)
+		})?),
+		None => None,
+	};
+	let fee_payer = fee_payer_keypair
+		.as_ref()
+		.map(|keypair| keypair.pubkey())
+		.unwrap_or(wallet_pubkey);
+	debug!("Fee payer: {}", fee_payer);
+
+	// Tokenizer instructions are authorised by the multisig vault when proposing through Squads
+	let authority = match args.squads_multisig {
+		Some(ref multisig) => squads::get_vault_address(multisig, 0).0,
+		None => wallet_pubkey,
+	};
+	// Instructions proposed through Squads execute from the vault, which must fund any accounts
+	// they create since the fee payer does not sign the vault transaction
+	let rent_payer = match args.squads_multisig {
+		Some(_) => authority,
+		None => fee_payer,
+	};
+
+	let mut report = new_report(&args, command);
+	if let Some(common_fields) = args.cmd.instruction_fields() {
+		report.field("Amount", common_fields.amount());
+	}
+	let mut instructions: Vec<Instruction> = Vec::new();
+	// Token account whose balance change is reported by `--show-delta`
+	let mut delta_token_account: Option<Pubkey> = None;
+	// Mints of the signer's token accounts that receive tokens and are created when missing,
+	// paired with their token program
+	let mut receiving_mints: Vec<(Pubkey, Pubkey)> = Vec::new();
+
+	let ctx = commands::Context {
+		program_id: program_id(),
+		authority,
+		underlying_token_program,
+	};
+	let transaction_options = TransactionOptions::new(&args);
+
+	let instruction: Instruction = match args.cmd {
+		Commands::Init(init) => match init {
+			Initialize::Tokenizer(common_fields) => {
+				let expiry = Expiry::from_i64(common_fields.expiry()?).map_err(|err| {
+					anyhow!("Unable to parse the given value to `Expiry`: {}", err)
+				})?;
+
+				let expiry_date = expiry
+					.to_expiry_date(timestamp)
+					.expect("Unable to convert expiry to expiry date");
+
+				if !args.allow_past_expiry {
+					ensure_expiry_in_future(expiry_date, timestamp)?;
+				}
+
+				if args.trace_derivations {
+					trace_derivations(
+						&common_fields.underlying_mint_address,
+						expiry_date,
+						&underlying_token_program,
+						&report,
+					);
+				}
+
+				let accounts =
+					ctx.init_accounts(&common_fields.underlying_mint_address, expiry_date);
+				report.field("Tokenizer Address", accounts.tokenizer);
+				report.field("Vault Address", accounts.underlying_vault);
+				report.field("Principal Mint Address", accounts.principal_mint);
+				report.field("Yield Mint Address", accounts.yield_mint);
+
+				commands::build_init_tokenizer(
+					&ctx,
+					&common_fields.underlying_mint_address,
+					expiry,
+					expiry_date,
+				)?
+			}
+			Initialize::Mints(common_fields) => {
+				let expiry = Expiry::from_i64(common_fields.expiry()?).map_err(|err| {
+					anyhow!("Unable to parse the given value to `Expiry`: {}", err)
+				})?;
+
+				let expiry_date = expiry
+					.to_expiry_date(timestamp)
+					.expect("Unable to convert expiry to expiry date");
+
+				if !args.allow_past_expiry {
+					ensure_expiry_in_future(expiry_date, timestamp)?;
+				}
+
+				if args.trace_derivations {
+					trace_derivations(
+						&common_fields.underlying_mint_address,
+						expiry_date,
+						&underlying_token_program,
+						&report,
+					);
+				}
+
+				let accounts =
+					ctx.init_accounts(&common_fields.underlying_mint_address, expiry_date);
+				report.field("Principal Mint Address", accounts.principal_mint);
+				report.field("Yield Mint Address", accounts.yield_mint);
+
+				commands::build_init_mints(
+					&ctx,
+					&common_fields.underlying_mint_address,
+					expiry,
+					expiry_date,
+				)?
+			}
+			Initialize::TokenizerMints(common_fields) => {
+				let expiry = Expiry::from_i64(common_fields.expiry()?).map_err(|err| {
+					anyhow!("Unable to parse the given value to `Expiry`: {}", err)
+				})?;
+
+				let expiry_date = expiry
+					.to_expiry_date(timestamp)
+					.expect("Unable to convert expiry to expiry date");
+
+				if !args.allow_past_expiry {
+					ensure_expiry_in_future(expiry_date, timestamp)?;
+				}
+
+				if args.trace_derivations {
+					trace_derivations(
+						&common_fields.underlying_mint_address,
+						expiry_date,
+						&underlying_token_program,
+						&report,
+					);
+				}
+
+				let accounts =
+					ctx.init_accounts(&common_fields.underlying_mint_address, expiry_date);
+				report.field("Tokenizer Address", accounts.tokenizer);
+				report.field("Vault Address", accounts.underlying_vault);
+				report.field("Principal Mint Address", accounts.principal_mint);
+				report.field("Yield Mint Address", accounts.yield_mint);
+
+				commands::build_init_tokenizer_and_mints(
+					&ctx,
+					&common_fields.underlying_mint_address,
+					expiry,
+					expiry_date,
+				)?
 			}
-			Terminate::TerminateTokenizer(common_fields) => instruction::terminate_tokenizer(
-				&common_fields.lysergic_tokenizer_address,
-				&wallet_pubkey,
-				&spl_associated_token_account::get_associated_token_address(
+			Initialize::Amm(_common_fields) => unimplemented!(),
+		},
+		Commands::Tokenize(tokenize) => match tokenize {
+			Tokenize::Deposit(DepositFields {
+				common_fields,
+				underlying_source,
+			}) => {
+				if !args.force && !args.offline {
+					ensure_tokenizer_active(
+						client,
+						&common_fields.lysergic_tokenizer_address,
+						timestamp,
+					)?;
+				}
+
+				let mut instruction =
+					commands::build_deposit(&ctx, &common_fields, common_fields.amount())?;
+
+				if !args.dry_run && !args.offline {
+					let source = underlying_source.unwrap_or_else(|| {
+						spl_associated_token_account::get_associated_token_address_with_program_id(
+							&authority,
+							&common_fields.underlying_mint_address(),
+							&underlying_token_program,
+						)
+					});
+					ensure_underlying_balance(client, &source, common_fields.amount())?;
+				}
+
+				if let Some(underlying_source) = underlying_source {
+					if !args.offline {
+						ensure_underlying_source(
+							client,
+							&underlying_source,
+							&common_fields.underlying_mint_address(),
+							&authority,
+						)?;
+					}
+
+					// The builder always derives the signer's ATA as the source, so swap it out
+					let user_underlying_token_address =
+						spl_associated_token_account::get_associated_token_address_with_program_id(
+							&authority,
+							&common_fields.underlying_mint_address(),
+							&underlying_token_program,
+						);
+
+					instruction
+						.accounts
+						.iter_mut()
+						.find(|account| account.pubkey == user_underlying_token_address)
+						.ok_or_else(|| {
+							anyhow!("`Deposit` instruction has no underlying source account")
+						})?
+						.pubkey = underlying_source;
+				}
+
+				instruction
+			}
+			Tokenize::Principal(common_fields) => {
+				if !args.force && !args.offline {
+					ensure_tokenizer_active(
+						client,
+						&common_fields.lysergic_tokenizer_address,
+						timestamp,
+					)?;
+				}
+
+				let (principal_mint_address, _) = get_principal_mint_address(
 					&common_fields.lysergic_tokenizer_address,
-					&common_fields.underlying_mint_address,
-				),
-			)
-			.map_err(|err| anyhow!("Unable to create `TerminateTokenizer` instruction: {}", err))?,
-			Terminate::TerminateMints(common_fields) => {
+					&program_id(),
+				);
+
+				receiving_mints.push((principal_mint_address, spl_token::id()));
+
+				commands::build_tokenize_principal(&ctx, &common_fields)?
+			}
+			Tokenize::Yield(common_fields) => {
+				if !args.force && !args.offline {
+					ensure_tokenizer_active(
+						client,
+						&common_fields.lysergic_tokenizer_address,
+						timestamp,
+					)?;
+				}
+
+				let (yield_mint_address, _) = get_yield_mint_address(
+					&common_fields.lysergic_tokenizer_address,
+					&program_id(),
+				);
+
+				receiving_mints.push((yield_mint_address, spl_token::id()));
+
+				commands::build_tokenize_yield(&ctx, &common_fields)?
+			}
+			Tokenize::PrincipalYield(DepositAndTokenizeFields {
+				common_fields,
+				tokenize_fraction,
+			}) => {
+				if !args.force && !args.offline {
+					ensure_tokenizer_active(
+						client,
+						&common_fields.lysergic_tokenizer_address,
+						timestamp,
+					)?;
+				}
+
+				let underlying_vault =
+					spl_associated_token_account::get_associated_token_address_with_program_id(
+						&common_fields.lysergic_tokenizer_address,
+						&common_fields.underlying_mint_address(),
+						&underlying_token_program,
+					);
+
+				report.field("Underlying vault", underlying_vault);
+
+				if args.validate_accounts && !args.offline {
+					let vault_account = client.get_account(&underlying_vault).map_err(|err| {
+						anyhow!("Unable to fetch vault {}: {}", underlying_vault, err)
+					})?;
+
+					let vault_mint = unpack_token_account(&vault_account.data)
+						.map_err(|err| {
+							anyhow!("Vault {} is not a token account: {}", underlying_vault, err)
+						})?
+						.mint;
+
+					if vault_mint != common_fields.underlying_mint_address() {
+						return Err(anyhow!(
+							"Vault {} holds mint {}, expected underlying mint {}",
+							underlying_vault,
+							vault_mint,
+							common_fields.underlying_mint_address()
+						));
+					}
+				}
+
+				let (principal_mint_address, _) = get_principal_mint_address(
+					&common_fields.lysergic_tokenizer_address,
+					&program_id(),
+				);
+
+				let (yield_mint_address, _) = get_yield_mint_address(
+					&common_fields.lysergic_tokenizer_address,
+					&program_id(),
+				);
+
+				let user_underlying_token_address =
+					spl_associated_token_account::get_associated_token_address_with_program_id(
+						&authority,
+						&common_fields.underlying_mint_address(),
+						&underlying_token_program,
+					);
+
+				receiving_mints.extend([
+					(principal_mint_address, spl_token::id()),
+					(yield_mint_address, spl_token::id()),
+				]);
+
+				if !args.dry_run && !args.offline {
+					ensure_underlying_balance(
+						client,
+						&user_underlying_token_address,
+						common_fields.amount(),
+					)?;
+				}
+
+				// The program tokenizes the full amount of a deposit, so a partial tokenization
+				// is expressed as a plain deposit of the remainder followed by a smaller
+				// deposit-and-tokenize.
+				let tokenize_amount = match tokenize_fraction {
+					Some(fraction) => apply_fraction(common_fields.amount(), fraction),
+					None => common_fields.amount(),
+				};
+
+				if tokenize_amount == 0 {
+					return Err(anyhow!(
+						"Tokenize fraction leaves nothing to tokenize out of {}",
+						common_fields.amount()
+					));
+				}
+
+				let deposit_amount = common_fields.amount() - tokenize_amount;
+				if deposit_amount > 0 {
+					report.field("Depositing without tokenizing", deposit_amount);
+
+					instructions.push(commands::build_deposit(
+						&ctx,
+						&common_fields,
+						deposit_amount,
+					)?);
+				}
+
+				commands::build_deposit_and_tokenize(&ctx, &common_fields, tokenize_amount)?
+			}
+			Tokenize::Batch(batch_fields) => {
+				let unsupported = [
+					("--squads-multisig", args.squads_multisig.is_some()),
+					("--offline", args.offline),
+					("--sign-only", args.sign_only),
+					("--blockhash", args.blockhash.is_some()),
+					("--signer", !args.signers.is_empty()),
+					("--estimate-only", args.estimate_only),
+					("--output-account-keys", args.output_account_keys),
+					("--output-signers-needed", args.output_signers_needed),
+					("--output-tx-base64", args.output_tx_base64),
+					("--output-tx-base58", args.output_tx_base58),
+					("--ws-url", args.ws_url.is_some()),
+					("--broadcast-to", !args.broadcast_to.is_empty()),
+					("--success-on", args.success_on != SuccessOn::Confirmed),
+					("--show-delta", args.show_delta),
+					("--report-account-slots", args.report_account_slots),
+					(
+						"--confirm-with-commitment-escalation",
+						args.confirm_with_commitment_escalation,
+					),
+					("--dump-ix-data", args.dump_ix_data),
+					("--show-accounts", args.show_accounts),
+					("--dump-compiled-message", args.dump_compiled_message),
+					("--check-ata-owner-program", args.check_ata_owner_program),
+					("--validate-accounts", args.validate_accounts),
+				];
+				if let Some((flag, _)) = unsupported.iter().find(|(_, set)| *set) {
+					return Err(anyhow!(
+						"`tokenize batch` sends a transaction per chunk of rows and cannot be used \
+						 with `{}`",
+						flag
+					));
+				}
+
+				let wallet_keypair = wallet_keypair
+					.as_ref()
+					.ok_or_else(|| anyhow!("Batch tokenization requires a keypair to sign with"))?;
+				let mut signers: Vec<&dyn Signer> = vec![wallet_keypair.as_ref()];
+				if let Some(ref fee_payer_keypair) = fee_payer_keypair {
+					signers.push(fee_payer_keypair);
+				}
+
+				let result = batch::tokenize_batch(
+					client,
+					&transaction_options,
+					&batch::BatchOptions {
+						max_operation_amount: args.max_operation_amount,
+						env_profile,
+						token_2022: args.token_2022,
+						force: args.force,
+						now: timestamp,
+						checkpoint: batch_fields.checkpoint.as_deref(),
+						output_summary: batch_fields.output_summary,
+					},
+					&signers,
+					&fee_payer,
+					&batch_fields.file,
+					&mut report,
+				);
+				return match result {
+					Ok(()) => report.finish(),
+					Err(err) => Err(report.fail(err)),
+				};
+			}
+		},
+		Commands::Redeem(redeem) => match redeem {
+			Redeem::All(redeem_all_fields) => {
+				if args.offline {
+					return Err(anyhow!(
+						"`redeem all` reads the signer's balances and cannot be built with \
+						 `--offline`, redeem an explicit amount instead"
+					));
+				}
+
+				let tokenizer_address = redeem_all_fields.lysergic_tokenizer_address;
+				let underlying_mint_address =
+					state::fetch_underlying_mint(client, &tokenizer_address)?;
+				let underlying_token_program =
+					mint_token_program(client, &underlying_mint_address, args.token_2022)?;
+				// The underlying mint is only known once the tokenizer has been read
+				let ctx = commands::Context {
+					underlying_token_program,
+					..ctx
+				};
+
 				let (principal_mint_address, _) =
-					get_principal_mint_address(&common_fields.lysergic_tokenizer_address);
+					get_principal_mint_address(&tokenizer_address, &program_id());
 				let (yield_mint_address, _) =
-					get_yield_mint_address(&common_fields.lysergic_tokenizer_address);
-				instruction::terminate_mints(
-					&common_fields.lysergic_tokenizer_address,
-					&wallet_pubkey,
-					&principal_mint_address,
-					&yield_mint_address,
-				)
-				.map_err(|err| anyhow!("Unable to create `TerminateMints` instruction: {}", err))?
+					get_yield_mint_address(&tokenizer_address, &program_id());
+
+				let user_underlying_token_address =
+					spl_associated_token_account::get_associated_token_address_with_program_id(
+						&authority,
+						&underlying_mint_address,
+						&underlying_token_program,
+					);
+				delta_token_account = Some(user_underlying_token_address);
+				receiving_mints.push((underlying_mint_address, underlying_token_program));
+
+				let user_principal_token_address =
+					spl_associated_token_account::get_associated_token_address(
+						&authority,
+						&principal_mint_address,
+					);
+
+				let user_yield_token_address =
+					spl_associated_token_account::get_associated_token_address(
+						&authority,
+						&yield_mint_address,
+					);
+
+				let principal_balance = token_balance(client, &user_principal_token_address, None)?;
+				let yield_balance = token_balance(client, &user_yield_token_address, None)?;
+
+				if principal_balance == 0 {
+					return Err(anyhow!(
+						"No principal tokens of tokenizer {} to redeem",
+						tokenizer_address
+					));
+				}
+
+				if yield_balance == 0 {
+					if !args.force && !args.offline {
+						ensure_tokenizer_expired(client, &tokenizer_address, timestamp)?;
+					}
+
+					ensure_within_operation_cap(
+						args.max_operation_amount,
+						env_profile,
+						&underlying_mint_address,
+						principal_balance,
+					)?;
+					report.field("Redeeming principal", principal_balance);
+
+					commands::build_redeem_principal(
+						&ctx,
+						&InstructionCommonFields::new(
+							tokenizer_address,
+							underlying_mint_address,
+							principal_balance,
+						),
+					)?
+				} else {
+					let amount = principal_balance.min(yield_balance);
+					if principal_balance != yield_balance {
+						eprintln!(
+							"Warning: holding {} principal and {} yield, redeeming {} of each and \
+							 leaving the remainder",
+							principal_balance, yield_balance, amount
+						);
+					}
+					ensure_within_operation_cap(
+						args.max_operation_amount,
+						env_profile,
+						&underlying_mint_address,
+						amount,
+					)?;
+					report.field("Redeeming principal and yield", amount);
+
+					commands::build_redeem_principal_and_yield(
+						&ctx,
+						&InstructionCommonFields::new(
+							tokenizer_address,
+							underlying_mint_address,
+							amount,
+						),
+					)?
+				}
+			}
+			Redeem::Principal(common_fields) => {
+				if !args.force && !args.offline {
+					ensure_tokenizer_expired(
+						client,
+						&common_fields.lysergic_tokenizer_address,
+						timestamp,
+					)?;
+				}
+
+				let underlying_vault_address =
+					spl_associated_token_account::get_associated_token_address_with_program_id(
+						&common_fields.lysergic_tokenizer_address,
+						&common_fields.underlying_mint_address(),
+						&underlying_token_program,
+					);
+
+				if !args.offline {
+					check_vault_liquidity(
+						client,
+						&common_fields.lysergic_tokenizer_address,
+						&underlying_vault_address,
+						common_fields.amount(),
+					)?;
+				}
+
+				delta_token_account = Some(
+					spl_associated_token_account::get_associated_token_address_with_program_id(
+						&authority,
+						&common_fields.underlying_mint_address(),
+						&underlying_token_program,
+					),
+				);
+				receiving_mints.push((
+					common_fields.underlying_mint_address(),
+					underlying_token_program,
+				));
+
+				commands::build_redeem_principal(&ctx, &common_fields)?
+			}
+			Redeem::Yield(common_fields) => {
+				delta_token_account = Some(
+					spl_associated_token_account::get_associated_token_address_with_program_id(
+						&authority,
+						&common_fields.underlying_mint_address(),
+						&underlying_token_program,
+					),
+				);
+				receiving_mints.push((
+					common_fields.underlying_mint_address(),
+					underlying_token_program,
+				));
+
+				commands::build_claim_yield(&ctx, &common_fields)?
+			}
+			Redeem::PrincipalYield(common_fields) => {
+				delta_token_account = Some(
+					spl_associated_token_account::get_associated_token_address_with_program_id(
+						&authority,
+						&common_fields.underlying_mint_address(),
+						&underlying_token_program,
+					),
+				);
+				receiving_mints.push((
+					common_fields.underlying_mint_address(),
+					underlying_token_program,
+				));
+
+				commands::build_redeem_principal_and_yield(&ctx, &common_fields)?
+			}
+		},
+		Commands::Terminate(terminate) => match terminate {
+			Terminate::Terminate(common_fields) => commands::build_terminate(&ctx, &common_fields)?,
+			Terminate::TerminateTokenizer(common_fields) => {
+				commands::build_terminate_tokenizer(&ctx, &common_fields)?
+			}
+			Terminate::TerminateMints(common_fields) => {
+				commands::build_terminate_mints(&ctx, &common_fields)?
 			}
 		},
 		Commands::Swap(swap) => match swap {
@@ -514,17 +3393,1030 @@ fn main() -> Result<()> {
 			Swap::Yield(_common_fields) => unimplemented!(),
 			Swap::Underlying(_common_fields) => unimplemented!(),
 		},
+		Commands::Replay(replay_fields) => {
+			let wallet_keypair = wallet_keypair
+				.as_ref()
+				.ok_or_else(|| anyhow!("Replay requires a keypair to sign with"))?;
+			replay(
+				client,
+				&transaction_options,
+				wallet_keypair,
+				&replay_fields.signature,
+				args.yes,
+				&mut report,
+			)?;
+			return report.finish();
+		}
+		Commands::InitDepositTokenize(init_deposit_fields) => {
+			if args.offline {
+				return Err(anyhow!(
+					"`init-deposit-tokenize` checks whether the tokenizer exists and cannot be \
+					 built with `--offline`"
+				));
+			}
+
+			ensure_within_operation_cap(
+				args.max_operation_amount,
+				env_profile,
+				&init_deposit_fields.init_fields.underlying_mint_address,
+				init_deposit_fields.amount,
+			)?;
+
+			let mut deposit_instructions = init_deposit::init_deposit_tokenize(
+				client,
+				&ctx,
+				&rent_payer,
+				&init_deposit_fields,
+				timestamp,
+				args.allow_past_expiry,
+				!args.dry_run,
+				&mut report,
+			)?;
+
+			let instruction = deposit_instructions
+				.pop()
+				.expect("the deposit instruction is always built");
+			instructions.extend(deposit_instructions);
+			instruction
+		}
+		Commands::Deposit(_) | Commands::Claim(_) => {
+			unreachable!("shortcuts are expanded after parsing")
+		}
+		Commands::Submit(_)
+		| Commands::Watch(_)
+		| Commands::ListMarkets(_)
+		| Commands::Derive(_)
+		| Commands::Status(_)
+		| Commands::Balances(_)
+		| Commands::Positions(_) => {
+			unreachable!("these commands return before the wallet is loaded")
+		}
+		Commands::Aliases(_) | Commands::DecodeTx(_) => {
+			unreachable!("these commands return before the config is loaded")
+		}
 	};
 
-	let mut transaction = Transaction::new_with_payer(&[instruction], Some(&wallet_pubkey));
-	let latest_blockchash = client
-		.get_latest_blockhash()
-		.map_err(|err| anyhow!("Unable to get latest blockhash: {}", err))?;
+	if !receiving_mints.is_empty() && !args.no_create_ata && !args.offline {
+		let create_instructions =
+			create_missing_token_accounts(client, &rent_payer, &authority, &receiving_mints)?;
+		instructions.splice(0..0, create_instructions);
+	}
 
-	transaction.sign(&[&wallet_keypair], latest_blockchash);
-	client
-		.send_and_confirm_transaction_with_spinner(&transaction)
-		.map_err(|err| anyhow!("Unable to send transaction: {}", err))?;
+	if args.show_accounts {
+		print_instruction_accounts(&instruction, &report);
+	}
 
-	Ok(())
+	instructions.push(instruction);
+
+	for instruction in &instructions {
+		debug!("Instruction for program {}", instruction.program_id);
+		for account in &instruction.accounts {
+			debug!(
+				"  {} signer: {} writable: {}",
+				account.pubkey, account.is_signer, account.is_writable
+			);
+		}
+	}
+
+	if args.dump_ix_data {
+		print_instruction_data(&instructions, &report)?;
+	}
+
+	if args.check_ata_owner_program {
+		check_token_account_programs(client, &instructions)?;
+	}
+
+	if let Some(ref multisig) = args.squads_multisig {
+		instructions =
+			squads::create_proposal(client, multisig, &wallet_pubkey, &instructions, &mut report)?;
+	}
+
+	transaction_options.wrap(&fee_payer, &mut instructions);
+
+	report.instructions(&instructions);
+
+	let mut transaction = Transaction::new_with_payer(&instructions, Some(&fee_payer));
+
+	if args.output_account_keys {
+		print_account_keys(&transaction.message, &mut report);
+		return report.finish();
+	}
+
+	if args.output_signers_needed {
+		report.list("Signers Needed", transaction.message.signer_keys());
+		return report.finish();
+	}
+
+	if let Some(blockhash) = args.blockhash.filter(|_| args.offline) {
+		transaction.message.recent_blockhash = blockhash;
+		report.raw(
+			"Transaction",
+			BASE64_STANDARD.encode(bincode::serialize(&transaction)?),
+		);
+		return report.finish();
+	}
+
+	let wallet_keypair = wallet_keypair.ok_or_else(|| anyhow!("Unable to read keypair file"))?;
+
+	let latest_blockchash = match args.blockhash {
+		Some(blockhash) => blockhash,
+		None => client
+			.get_latest_blockhash()
+			.map_err(|err| anyhow!("Unable to get latest blockhash: {}", err))?,
+	};
+
+	if args.dump_compiled_message {
+		transaction.message.recent_blockhash = latest_blockchash;
+		print_compiled_message(&transaction.message, &report);
+	}
+
+	if args.dry_run || args.simulate_at_slot.is_some() {
+		simulate(client, &transaction, args.simulate_at_slot, &mut report)?;
+		return report.finish();
+	}
+
+	if args.estimate_only {
+		transaction.message.recent_blockhash = latest_blockchash;
+		estimate(client, &transaction, &mut report)?;
+		return report.finish();
+	}
+
+	if args.sign_only {
+		transaction.partial_sign(&[wallet_keypair.as_ref()], latest_blockchash);
+		if let Some(ref fee_payer_keypair) = fee_payer_keypair {
+			transaction.partial_sign(&[fee_payer_keypair], latest_blockchash);
+		}
+		print_sign_only(&transaction, &mut report);
+		return report.finish();
+	}
+
+	if args.max_fee.is_some() || args.verbose {
+		transaction.message.recent_blockhash = latest_blockchash;
+		ensure_fee_within(client, &transaction.message, args.max_fee)?;
+	}
+
+	let delta_token_account = delta_token_account.filter(|_| args.show_delta);
+	let balance_before = match delta_token_account {
+		Some(ref token_account) => token_balance(client, token_account, None)?,
+		None => 0,
+	};
+
+	let presigners: Vec<Presigner> = args
+		.signers
+		.iter()
+		.map(|(pubkey, signature)| Presigner::new(pubkey, signature))
+		.collect();
+
+	let mut signers: Vec<&dyn Signer> = vec![wallet_keypair.as_ref()];
+	if let Some(ref fee_payer_keypair) = fee_payer_keypair {
+		signers.push(fee_payer_keypair);
+	}
+	signers.extend(presigners.iter().map(|presigner| presigner as &dyn Signer));
+
+	transaction
+		.try_sign(&signers, latest_blockchash)
+		.map_err(|err| anyhow!("Unable to sign transaction: {}", err))?;
+
+	if args.output_tx_base64 || args.output_tx_base58 {
+		let serialized_transaction = bincode::serialize(&transaction)?;
+
+		if args.output_tx_base64 {
+			report.raw(
+				"Transaction Base64",
+				BASE64_STANDARD.encode(&serialized_transaction),
+			);
+		}
+
+		if args.output_tx_base58 {
+			report.raw(
+				"Transaction Base58",
+				bs58::encode(&serialized_transaction).into_string(),
+			);
+		}
+
+		return report.finish();
+	}
+
+	// Presigned signatures and a caller-supplied blockhash are tied to the original blockhash,
+	// so those transactions are resent unchanged
+	let refresh_blockhash = args.blockhash.is_none() && args.signers.is_empty();
+	let signature = transaction_options.send(
+		client,
+		&mut transaction,
+		&signers,
+		refresh_blockhash,
+		&mut report,
+	)?;
+
+	if let Some(ref token_account) = delta_token_account {
+		let landed_slot = landed_slot(client, &signature)?;
+		let balance_after = token_balance(client, token_account, landed_slot)?;
+		report.field(
+			"Underlying received",
+			balance_after as i128 - balance_before as i128,
+		);
+	}
+
+	if args.report_account_slots {
+		print_account_slots(client, &transaction.message, &signature, &mut report)?;
+	}
+
+	if args.confirm_with_commitment_escalation {
+		let confirmed_slot =
+			wait_for_commitment(client, &signature, CommitmentConfig::confirmed())?;
+		report.field("Confirmed at slot", confirmed_slot);
+
+		let finalized_slot =
+			wait_for_commitment(client, &signature, CommitmentConfig::finalized())?;
+		report.field("Finalized at slot", finalized_slot);
+	} else if args.success_on == SuccessOn::Finalized {
+		wait_for_commitment(client, &signature, CommitmentConfig::finalized())?;
+	}
+
+	report.finish()
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		rand::{rngs::StdRng, SeedableRng},
+		rpc::mock::MockRpc,
+		solana_sdk::signature::{write_keypair_file, Keypair},
+		std::fs,
+	};
+
+	const AMOUNT: u64 = 100;
+
+	/// A wallet keypair on disk and a mock cluster where the wallet holds enough underlying to
+	/// deposit.
+	struct Harness {
+		client: MockRpc,
+		keypair_path: String,
+		wallet: Pubkey,
+		tokenizer: Pubkey,
+		underlying_mint: Pubkey,
+	}
+
+	impl Harness {
+		fn new() -> Self {
+			let wallet = Keypair::new();
+			let keypair_path = std::env::temp_dir()
+				.join(format!("sclr-test-{}.json", wallet.pubkey()))
+				.to_str()
+				.expect("temp dir is valid UTF-8")
+				.to_string();
+			write_keypair_file(&wallet, &keypair_path).expect("temp dir is writable");
+
+			let underlying_mint = Pubkey::new_unique();
+			let client = MockRpc::default().with_token_account(
+				spl_associated_token_account::get_associated_token_address(
+					&wallet.pubkey(),
+					&underlying_mint,
+				),
+				underlying_mint,
+				wallet.pubkey(),
+				AMOUNT,
+			);
+
+			Self {
+				client,
+				keypair_path,
+				wallet: wallet.pubkey(),
+				tokenizer: Pubkey::new_unique(),
+				underlying_mint,
+			}
+		}
+
+		fn principal_mint(&self) -> Pubkey {
+			get_principal_mint_address(&self.tokenizer, &program_id()).0
+		}
+
+		fn yield_mint(&self) -> Pubkey {
+			get_yield_mint_address(&self.tokenizer, &program_id()).0
+		}
+
+		/// The tokenizer's underlying vault. The underlying mint does not exist on the mock
+		/// cluster, so it is taken to be a legacy token mint.
+		fn vault(&self) -> Pubkey {
+			spl_associated_token_account::get_associated_token_address(
+				&self.tokenizer,
+				&self.underlying_mint,
+			)
+		}
+
+		/// The wallet's token account of `mint`.
+		fn wallet_account(&self, mint: &Pubkey) -> Pubkey {
+			spl_associated_token_account::get_associated_token_address(&self.wallet, mint)
+		}
+
+		/// Runs `command` with `--force`, so no tokenizer account is needed, and returns the
+		/// instructions of the single transaction it sent.
+		fn send(&self, command: &[&str]) -> Vec<Instruction> {
+			let args = Cli::try_parse_from(["sclr", "--force"].iter().chain(command).copied())
+				.expect("arguments parse");
+			execute(
+				args,
+				"test",
+				&config::EnvProfile::default(),
+				&self.keypair_path,
+				&self.client,
+				&TimeSource::Local,
+			)
+			.expect("command succeeds");
+
+			let sent = self.client.sent.lock().unwrap();
+			assert_eq!(sent.len(), 1, "expected exactly one transaction");
+			decode::decompile_message(&sent[0].message).expect("sent message decompiles")
+		}
+
+		/// Runs an instruction command on the harness's tokenizer and amount.
+		fn send_instruction(&self, subcommand: &[&str]) -> Vec<Instruction> {
+			let tokenizer = self.tokenizer.to_string();
+			let amount = AMOUNT.to_string();
+			let underlying_mint = self.underlying_mint.to_string();
+
+			let mut command = subcommand.to_vec();
+			command.extend([
+				tokenizer.as_str(),
+				amount.as_str(),
+				"--underlying-mint",
+				underlying_mint.as_str(),
+			]);
+			self.send(&command)
+		}
+
+		/// Runs a terminate command on the harness's tokenizer.
+		fn send_terminate(&self, subcommand: &str) -> Vec<Instruction> {
+			let tokenizer = self.tokenizer.to_string();
+			let underlying_mint = self.underlying_mint.to_string();
+
+			self.send(&["terminate", subcommand, &tokenizer, &underlying_mint])
+		}
+	}
+
+	impl Drop for Harness {
+		fn drop(&mut self) {
+			let _ = fs::remove_file(&self.keypair_path);
+		}
+	}
+
+	/// Returns the one tokenizer instruction among `instructions`, checking it decodes.
+	fn tokenizer_instruction(instructions: &[Instruction]) -> &Instruction {
+		let mut tokenizer_instructions = instructions
+			.iter()
+			.filter(|instruction| instruction.program_id == program_id());
+		let instruction = tokenizer_instructions
+			.next()
+			.expect("a tokenizer instruction is sent");
+		assert!(
+			tokenizer_instructions.next().is_none(),
+			"expected one tokenizer instruction"
+		);
+
+		decode::decode_instruction(&instruction.data).expect("tokenizer instruction decodes");
+		instruction
+	}
+
+	/// Reads the amount the tokenizer instruction data ends with.
+	fn amount_of(instruction: &Instruction) -> u64 {
+		let amount = instruction.data[instruction.data.len() - 8..]
+			.try_into()
+			.expect("instruction data ends with the amount");
+		u64::from_le_bytes(amount)
+	}
+
+	/// Returns the token accounts `instructions` create, sorted.
+	fn created_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+		let mut created: Vec<Pubkey> = instructions
+			.iter()
+			.filter(|instruction| instruction.program_id == spl_associated_token_account::id())
+			.map(|instruction| instruction.accounts[1].pubkey)
+			.collect();
+		created.sort();
+		created
+	}
+
+	/// Asserts the wallet signs `instruction`, which references each of `present` and none of
+	/// `absent`.
+	fn assert_accounts(
+		harness: &Harness,
+		instruction: &Instruction,
+		present: &[Pubkey],
+		absent: &[Pubkey],
+	) {
+		let references = |pubkey: &Pubkey| {
+			instruction
+				.accounts
+				.iter()
+				.any(|account| account.pubkey == *pubkey)
+		};
+
+		assert!(instruction
+			.accounts
+			.iter()
+			.any(|account| account.pubkey == harness.wallet && account.is_signer));
+		for pubkey in present {
+			assert!(references(pubkey), "expected {} to be referenced", pubkey);
+		}
+		for pubkey in absent {
+			assert!(
+				!references(pubkey),
+				"expected {} not to be referenced",
+				pubkey
+			);
+		}
+	}
+
+	fn sorted(mut pubkeys: Vec<Pubkey>) -> Vec<Pubkey> {
+		pubkeys.sort();
+		pubkeys
+	}
+
+	#[test]
+	fn tokenize_deposit_moves_underlying_into_the_vault() {
+		let harness = Harness::new();
+		let instructions = harness.send_instruction(&["tokenize", "deposit"]);
+		let instruction = tokenizer_instruction(&instructions);
+
+		assert!(created_accounts(&instructions).is_empty());
+		assert_accounts(
+			&harness,
+			instruction,
+			&[harness.tokenizer, harness.vault(), harness.underlying_mint],
+			&[harness.principal_mint(), harness.yield_mint()],
+		);
+		assert_eq!(amount_of(instruction), AMOUNT);
+	}
+
+	#[test]
+	fn tokenize_principal_mints_into_a_new_principal_account() {
+		let harness = Harness::new();
+		let instructions = harness.send_instruction(&["tokenize", "principal"]);
+		let instruction = tokenizer_instruction(&instructions);
+		let principal_account = harness.wallet_account(&harness.principal_mint());
+
+		assert_eq!(created_accounts(&instructions), [principal_account]);
+		assert_accounts(
+			&harness,
+			instruction,
+			&[harness.principal_mint(), principal_account],
+			&[harness.yield_mint(), harness.vault()],
+		);
+		assert_eq!(amount_of(instruction), AMOUNT);
+	}
+
+	#[test]
+	fn tokenize_yield_mints_into_a_new_yield_account() {
+		let harness = Harness::new();
+		let instructions = harness.send_instruction(&["tokenize", "yield"]);
+		let instruction = tokenizer_instruction(&instructions);
+		let yield_account = harness.wallet_account(&harness.yield_mint());
+
+		assert_eq!(created_accounts(&instructions), [yield_account]);
+		assert_accounts(
+			&harness,
+			instruction,
+			&[harness.yield_mint(), yield_account],
+			&[harness.principal_mint(), harness.vault()],
+		);
+		assert_eq!(amount_of(instruction), AMOUNT);
+	}
+
+	#[test]
+	fn tokenize_principal_yield_deposits_into_both_new_accounts() {
+		let harness = Harness::new();
+		let instructions = harness.send_instruction(&["tokenize", "principal-yield"]);
+		let instruction = tokenizer_instruction(&instructions);
+		let principal_account = harness.wallet_account(&harness.principal_mint());
+		let yield_account = harness.wallet_account(&harness.yield_mint());
+
+		assert_eq!(
+			created_accounts(&instructions),
+			sorted(vec![principal_account, yield_account])
+		);
+		assert_accounts(
+			&harness,
+			instruction,
+			&[
+				harness.vault(),
+				harness.wallet_account(&harness.underlying_mint),
+				principal_account,
+				yield_account,
+			],
+			&[],
+		);
+		assert_eq!(amount_of(instruction), AMOUNT);
+	}
+
+	#[test]
+	fn redeem_principal_pays_out_of_the_vault() {
+		let harness = Harness::new();
+		let instructions = harness.send_instruction(&["redeem", "principal"]);
+		let instruction = tokenizer_instruction(&instructions);
+
+		// The wallet already holds the underlying account it is paid into
+		assert!(created_accounts(&instructions).is_empty());
+		assert_accounts(
+			&harness,
+			instruction,
+			&[
+				harness.vault(),
+				harness.principal_mint(),
+				harness.wallet_account(&harness.principal_mint()),
+				harness.wallet_account(&harness.underlying_mint),
+			],
+			&[harness.yield_mint()],
+		);
+		assert_eq!(amount_of(instruction), AMOUNT);
+	}
+
+	#[test]
+	fn redeem_yield_claims_without_touching_principal() {
+		let harness = Harness::new();
+		let instructions = harness.send_instruction(&["redeem", "yield"]);
+		let instruction = tokenizer_instruction(&instructions);
+
+		assert!(created_accounts(&instructions).is_empty());
+		assert_accounts(
+			&harness,
+			instruction,
+			&[
+				harness.yield_mint(),
+				harness.wallet_account(&harness.yield_mint()),
+				harness.wallet_account(&harness.underlying_mint),
+			],
+			&[harness.principal_mint(), harness.vault()],
+		);
+		assert_eq!(amount_of(instruction), AMOUNT);
+	}
+
+	#[test]
+	fn redeem_principal_yield_burns_both_tokens() {
+		let harness = Harness::new();
+		let instructions = harness.send_instruction(&["redeem", "principal-yield"]);
+		let instruction = tokenizer_instruction(&instructions);
+
+		assert!(created_accounts(&instructions).is_empty());
+		assert_accounts(
+			&harness,
+			instruction,
+			&[
+				harness.vault(),
+				harness.wallet_account(&harness.principal_mint()),
+				harness.wallet_account(&harness.yield_mint()),
+				harness.wallet_account(&harness.underlying_mint),
+			],
+			&[],
+		);
+		assert_eq!(amount_of(instruction), AMOUNT);
+	}
+
+	#[test]
+	fn terminate_closes_the_tokenizer_token_accounts() {
+		let harness = Harness::new();
+		let instructions = harness.send_terminate("terminate");
+		let instruction = tokenizer_instruction(&instructions);
+		let tokenizer_account = |mint: &Pubkey| {
+			spl_associated_token_account::get_associated_token_address(&harness.tokenizer, mint)
+		};
+
+		assert!(created_accounts(&instructions).is_empty());
+		assert_accounts(
+			&harness,
+			instruction,
+			&[
+				harness.vault(),
+				tokenizer_account(&harness.principal_mint()),
+				tokenizer_account(&harness.yield_mint()),
+			],
+			&[
+				harness.wallet_account(&harness.principal_mint()),
+				harness.wallet_account(&harness.yield_mint()),
+			],
+		);
+	}
+
+	#[test]
+	fn terminate_tokenizer_closes_only_the_vault() {
+		let harness = Harness::new();
+		let instructions = harness.send_terminate("terminate-tokenizer");
+		let instruction = tokenizer_instruction(&instructions);
+
+		assert_accounts(
+			&harness,
+			instruction,
+			&[harness.tokenizer, harness.vault()],
+			&[harness.principal_mint(), harness.yield_mint()],
+		);
+	}
+
+	#[test]
+	fn terminate_mints_closes_only_the_mints() {
+		let harness = Harness::new();
+		let instructions = harness.send_terminate("terminate-mints");
+		let instruction = tokenizer_instruction(&instructions);
+
+		assert_accounts(
+			&harness,
+			instruction,
+			&[harness.principal_mint(), harness.yield_mint()],
+			&[harness.vault()],
+		);
+	}
+
+	const CONFIG_RPC_URL: &str = "https://api.mainnet-beta.solana.com";
+	const PROFILE_RPC_URL: &str = "https://api.devnet.solana.com";
+	const LOCAL_RPC_URL: &str = "http://localhost:8899";
+
+	#[test]
+	fn rpc_flag_overrides_config_and_profile() {
+		assert_eq!(
+			resolve_rpc_urls(
+				&[LOCAL_RPC_URL.to_string()],
+				Some(PROFILE_RPC_URL),
+				Some(CONFIG_RPC_URL)
+			),
+			Some(vec![LOCAL_RPC_URL.to_string()])
+		);
+	}
+
+	#[test]
+	fn rpc_url_falls_back_to_profile_then_config() {
+		assert_eq!(
+			resolve_rpc_urls(&[], Some(PROFILE_RPC_URL), Some(CONFIG_RPC_URL)),
+			Some(vec![PROFILE_RPC_URL.to_string()])
+		);
+		assert_eq!(
+			resolve_rpc_urls(&[], None, Some(CONFIG_RPC_URL)),
+			Some(vec![CONFIG_RPC_URL.to_string()])
+		);
+	}
+
+	#[test]
+	fn extra_rpc_flags_follow_as_fallbacks() {
+		assert_eq!(
+			resolve_rpc_urls(
+				&[LOCAL_RPC_URL.to_string(), PROFILE_RPC_URL.to_string()],
+				None,
+				Some(CONFIG_RPC_URL)
+			),
+			Some(vec![LOCAL_RPC_URL.to_string(), PROFILE_RPC_URL.to_string()])
+		);
+	}
+
+	#[test]
+	fn rpc_url_is_missing_without_any_source() {
+		assert_eq!(resolve_rpc_urls(&[], None, None), None);
+	}
+
+	/// Wraps a single tokenizer instruction with the transaction options parsed from `flags`.
+	fn wrap_with_flags(flags: &[&str]) -> (Instruction, Vec<Instruction>) {
+		let harness = Harness::new();
+		let args = Cli::try_parse_from(
+			["sclr"]
+				.iter()
+				.chain(flags)
+				.chain(&["redeem", "yield", "11111111111111111111111111111111", "1"])
+				.copied(),
+		)
+		.expect("arguments parse");
+
+		let ctx = commands::Context {
+			program_id: program_id(),
+			authority: harness.wallet,
+			underlying_token_program: spl_token::id(),
+		};
+		let fields =
+			InstructionCommonFields::new(harness.tokenizer, harness.underlying_mint, AMOUNT);
+
+		let instruction = commands::build_claim_yield(&ctx, &fields).unwrap();
+		let mut instructions = vec![instruction.clone()];
+		TransactionOptions::new(&args).wrap(&ctx.authority, &mut instructions);
+		(instruction, instructions)
+	}
+
+	#[test]
+	fn compute_budget_instructions_come_first() {
+		let (instruction, instructions) = wrap_with_flags(&[
+			"--compute-unit-limit",
+			"200000",
+			"--compute-unit-price",
+			"1000",
+		]);
+
+		assert_eq!(
+			instructions,
+			vec![
+				ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+				ComputeBudgetInstruction::set_compute_unit_price(1_000),
+				instruction,
+			]
+		);
+	}
+
+	#[test]
+	fn compute_budget_flag_matches_separate_flags() {
+		let (instruction, instructions) =
+			wrap_with_flags(&["--compute-budget", "limit=200000,price=1000"]);
+
+		assert_eq!(
+			instructions,
+			vec![
+				ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+				ComputeBudgetInstruction::set_compute_unit_price(1_000),
+				instruction,
+			]
+		);
+	}
+
+	#[test]
+	fn no_compute_budget_instructions_without_flags() {
+		let (instruction, instructions) = wrap_with_flags(&[]);
+
+		assert_eq!(instructions, vec![instruction]);
+	}
+
+	#[test]
+	fn backoff_is_fully_jittered_up_to_the_doubled_backoff() {
+		let mut rng = StdRng::seed_from_u64(7);
+
+		for retry in 1..=5 {
+			let max_backoff = Duration::from_millis(500) * 2u32.pow(retry - 1);
+			for _ in 0..100 {
+				assert!(backoff_delay(retry, &mut rng) <= max_backoff);
+			}
+		}
+	}
+
+	#[test]
+	fn backoff_spans_the_lower_half_of_the_range() {
+		let mut rng = StdRng::seed_from_u64(7);
+
+		assert!((0..100).any(|_| backoff_delay(3, &mut rng) < Duration::from_millis(1_000)));
+	}
+
+	#[test]
+	fn backoff_is_deterministic_under_a_seed() {
+		let delays = |seed| {
+			let mut rng = StdRng::seed_from_u64(seed);
+			(1..=5)
+				.map(|retry| backoff_delay(retry, &mut rng))
+				.collect::<Vec<_>>()
+		};
+
+		assert_eq!(delays(42), delays(42));
+		assert_ne!(delays(42), delays(43));
+	}
+
+	#[test]
+	fn backoff_saturates_instead_of_overflowing() {
+		let mut rng = StdRng::seed_from_u64(7);
+
+		backoff_delay(u32::MAX, &mut rng);
+	}
+
+	#[test]
+	fn commitment_is_reported_at_the_landed_slot() {
+		assert_eq!(
+			wait_for_commitment(
+				&MockRpc::default(),
+				&Signature::default(),
+				CommitmentConfig::finalized()
+			)
+			.unwrap(),
+			MockRpc::LANDED_SLOT
+		);
+	}
+
+	#[test]
+	fn blockhash_not_found_is_an_expired_blockhash() {
+		assert!(is_blockhash_expired(&ClientError::from(
+			TransactionError::BlockhashNotFound
+		)));
+		assert!(is_blockhash_expired(&ClientError::from(
+			RpcError::RpcResponseError {
+				code: -32002,
+				message: "Transaction simulation failed: Blockhash not found".to_string(),
+				data: RpcResponseErrorData::Empty,
+			}
+		)));
+	}
+
+	#[test]
+	fn other_user_facing_errors_are_not_an_expired_blockhash() {
+		assert!(!is_blockhash_expired(&ClientError::from(
+			RpcError::ForUser(
+				"unable to confirm transaction. This can happen in situations such as transaction \
+			 expiration and insufficient fee-payer funds"
+					.to_string()
+			)
+		)));
+		assert!(!is_blockhash_expired(&ClientError::from(
+			TransactionError::InsufficientFundsForFee
+		)));
+	}
+
+	#[test]
+	fn ui_amount_scales_by_decimals() {
+		assert_eq!(parse_ui_amount("1.5", 6).unwrap(), 1_500_000);
+		assert_eq!(parse_ui_amount(".5", 6).unwrap(), 500_000);
+		assert_eq!(parse_ui_amount("1.", 6).unwrap(), 1_000_000);
+		assert_eq!(parse_ui_amount("42", 0).unwrap(), 42);
+	}
+
+	#[test]
+	fn ui_amount_rejects_more_digits_than_decimals() {
+		assert!(parse_ui_amount("1.0000001", 6).is_err());
+		assert!(parse_ui_amount("1.5", 0).is_err());
+	}
+
+	#[test]
+	fn ui_amount_rejects_malformed_amounts() {
+		assert!(parse_ui_amount("-1", 6).is_err());
+		assert!(parse_ui_amount("-0.5", 6).is_err());
+		assert!(parse_ui_amount("", 6).is_err());
+		assert!(parse_ui_amount(".", 6).is_err());
+		assert!(parse_ui_amount("1e3", 6).is_err());
+	}
+
+	#[test]
+	fn ui_amount_rejects_overflow() {
+		assert_eq!(
+			parse_ui_amount("18446744073709.551615", 6).unwrap(),
+			u64::MAX
+		);
+		assert!(parse_ui_amount("18446744073709.551616", 6).is_err());
+		assert!(parse_ui_amount("18446744073709551616", 0).is_err());
+	}
+
+	#[test]
+	fn redemptions_are_denominated_in_the_redeemed_mint() {
+		let tokenizer = Pubkey::new_unique();
+		let parse = |command: &[&str]| {
+			Cli::try_parse_from(["sclr"].iter().chain(command).copied())
+				.expect("arguments parse")
+				.cmd
+		};
+		let tokenizer_arg = tokenizer.to_string();
+
+		assert_eq!(
+			parse(&["redeem", "principal", &tokenizer_arg, "1"]).redeemed_mint(),
+			Some(get_principal_mint_address(&tokenizer, &program_id()).0)
+		);
+		assert_eq!(
+			parse(&["redeem", "principal-yield", &tokenizer_arg, "1"]).redeemed_mint(),
+			Some(get_principal_mint_address(&tokenizer, &program_id()).0)
+		);
+		assert_eq!(
+			parse(&["redeem", "yield", &tokenizer_arg, "1"]).redeemed_mint(),
+			Some(get_yield_mint_address(&tokenizer, &program_id()).0)
+		);
+		assert_eq!(
+			parse(&["tokenize", "deposit", &tokenizer_arg, "1"]).redeemed_mint(),
+			None
+		);
+	}
+
+	/// Runs `redeem <subcommand> --ui-amount 1.5` where the underlying, principal and yield mints
+	/// all have different decimals, returning the amount the instruction carries.
+	fn redeemed_ui_amount(subcommand: &str) -> u64 {
+		let mut harness = Harness::new();
+		let (principal_mint, _) = get_principal_mint_address(&harness.tokenizer, &program_id());
+		let (yield_mint, _) = get_yield_mint_address(&harness.tokenizer, &program_id());
+		harness.client = std::mem::take(&mut harness.client)
+			.with_mint(harness.underlying_mint, 6)
+			.with_mint(principal_mint, 9)
+			.with_mint(yield_mint, 3);
+
+		let tokenizer = harness.tokenizer.to_string();
+		let underlying_mint = harness.underlying_mint.to_string();
+		let instructions = harness.send(&[
+			"redeem",
+			subcommand,
+			&tokenizer,
+			"--ui-amount",
+			"1.5",
+			"--underlying-mint",
+			&underlying_mint,
+		]);
+
+		amount_of(tokenizer_instruction(&instructions))
+	}
+
+	#[test]
+	fn ui_amount_uses_the_decimals_of_the_redeemed_mint() {
+		assert_eq!(redeemed_ui_amount("principal"), 1_500_000_000);
+		assert_eq!(redeemed_ui_amount("principal-yield"), 1_500_000_000);
+		assert_eq!(redeemed_ui_amount("yield"), 1_500);
+	}
+
+	#[test]
+	fn payer_and_fee_payer_cannot_both_read_stdin() {
+		let harness = Harness::new();
+		let tokenizer = harness.tokenizer.to_string();
+		let underlying_mint = harness.underlying_mint.to_string();
+		let args = Cli::try_parse_from([
+			"sclr",
+			"--fee-payer",
+			"-",
+			"tokenize",
+			"deposit",
+			&tokenizer,
+			"1",
+			"--underlying-mint",
+			&underlying_mint,
+		])
+		.expect("arguments parse");
+
+		let err = execute(
+			args,
+			"test",
+			&config::EnvProfile::default(),
+			"-",
+			&harness.client,
+			&TimeSource::Local,
+		)
+		.expect_err("two keypairs cannot share stdin");
+		assert!(err.to_string().contains("stdin"), "{}", err);
+		assert!(harness.client.sent.lock().unwrap().is_empty());
+	}
+
+	#[test]
+	fn batch_summary_totals_the_run() {
+		let harness = Harness::new();
+		let batch_path = std::env::temp_dir().join(format!("sclr-batch-{}.csv", harness.tokenizer));
+		let ledger_path =
+			std::env::temp_dir().join(format!("sclr-ledger-{}.jsonl", harness.tokenizer));
+		let batch_path = batch_path.to_str().expect("temp dir is valid UTF-8");
+		let ledger_path = ledger_path.to_str().expect("temp dir is valid UTF-8");
+		fs::write(
+			batch_path,
+			format!(
+				"tokenizer,amount,underlying_mint\n{0},40,{1}\n{0},50,{1}\n",
+				harness.tokenizer, harness.underlying_mint
+			),
+		)
+		.expect("temp dir is writable");
+
+		let args = Cli::try_parse_from([
+			"sclr",
+			"--force",
+			"--output-file",
+			ledger_path,
+			"tokenize",
+			"batch",
+			batch_path,
+			"--output-summary",
+		])
+		.expect("arguments parse");
+		let result = execute(
+			args,
+			"tokenize batch",
+			&config::EnvProfile::default(),
+			&harness.keypair_path,
+			&harness.client,
+			&TimeSource::Local,
+		);
+		let ledger = fs::read_to_string(ledger_path);
+		let _ = fs::remove_file(batch_path);
+		let _ = fs::remove_file(ledger_path);
+		result.expect("batch succeeds");
+
+		let record: serde_json::Value =
+			serde_json::from_str(ledger.expect("ledger is written").trim()).unwrap();
+		assert_eq!(record["total_rows"], "2");
+		assert_eq!(record["succeeded"], "2");
+		assert_eq!(record["failed"], "0");
+		assert_eq!(record["skipped"], "0");
+		assert_eq!(record["total_amount"], "90");
+		assert_eq!(
+			record["fees_paid"],
+			format!(
+				"{} lamports",
+				5_000 * harness.client.sent.lock().unwrap().len()
+			)
+		);
+		assert!(record["elapsed"]
+			.as_str()
+			.is_some_and(|elapsed| elapsed.ends_with('s')));
+	}
+
+	#[test]
+	fn rate_limits_are_recognized() {
+		let response_error = |code| {
+			ClientError::from(RpcError::RpcResponseError {
+				code,
+				message: "Too many requests for a specific RPC call".to_string(),
+				data: RpcResponseErrorData::Empty,
+			})
+		};
+
+		assert!(is_rate_limited(&response_error(429)));
+		assert!(!is_rate_limited(&response_error(-32002)));
+		assert!(!is_rate_limited(&ClientError::from(io::Error::new(
+			io::ErrorKind::ConnectionRefused,
+			"connection refused",
+		))));
+	}
 }
@@ -0,0 +1,268 @@
+use {
+	crate::error::CliError,
+	anyhow::{anyhow, Error, Result},
+	base64::{prelude::BASE64_STANDARD, Engine},
+	chrono::Utc,
+	clap::ValueEnum,
+	serde::Serialize,
+	serde_json::{json, Map, Value},
+	solana_program::instruction::Instruction,
+	std::{fmt::Display, fs::OpenOptions, io::Write},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+	/// Labeled lines for people
+	#[default]
+	Display,
+	/// A single JSON object printed once the command finishes
+	Json,
+}
+
+/// Collects the values a command reports, printing them as they arrive in display mode or
+/// gathering them into one JSON object in json mode.
+pub struct Report {
+	format: OutputFormat,
+	fields: Map<String, Value>,
+	/// File the finished report is appended to, with the name of the command that produced it
+	ledger: Option<(String, String)>,
+	/// Keys the json output is narrowed to, all of them when empty
+	selected: Vec<String>,
+}
+
+impl Report {
+	pub fn new(format: OutputFormat) -> Self {
+		Self {
+			format,
+			fields: Map::new(),
+			ledger: None,
+			selected: Vec::new(),
+		}
+	}
+
+	/// Appends the finished report as one JSON line to `path`, when given, tagged with
+	/// `command`.
+	pub fn ledger(mut self, path: Option<&str>, command: &str) -> Self {
+		self.ledger = path.map(|path| (path.to_string(), command.to_string()));
+		self
+	}
+
+	/// Narrows the json output to `fields`, named by their key or label. The ledger still records
+	/// every field.
+	pub fn select(mut self, fields: &[String]) -> Self {
+		self.selected = fields.iter().map(|field| key(field.trim())).collect();
+		self
+	}
+
+	pub fn is_json(&self) -> bool {
+		self.format == OutputFormat::Json
+	}
+
+	/// Reports `value` under `label`, keyed as the snake-cased label in json mode and the
+	/// ledger.
+	pub fn field(&mut self, label: &str, value: impl Display) {
+		if self.format == OutputFormat::Display {
+			println!("{}: {}", label, value);
+		}
+
+		self.fields
+			.insert(key(label), Value::String(value.to_string()));
+	}
+
+	/// Reports `value` under `label` like `field`, but prints it bare in display mode so it can
+	/// be piped into another command.
+	pub fn raw(&mut self, label: &str, value: impl Display) {
+		if self.format == OutputFormat::Display {
+			println!("{}", value);
+		}
+
+		self.fields
+			.insert(key(label), Value::String(value.to_string()));
+	}
+
+	/// Reports `values` under `label`, one per line in display mode and as an array in json
+	/// mode.
+	pub fn list<T: Display>(&mut self, label: &str, values: impl IntoIterator<Item = T>) {
+		let values: Vec<String> = values.into_iter().map(|value| value.to_string()).collect();
+
+		if self.format == OutputFormat::Display {
+			println!("{}:", label);
+			for value in &values {
+				println!("  {}", value);
+			}
+		}
+
+		self.fields.insert(
+			key(label),
+			Value::Array(values.into_iter().map(Value::String).collect()),
+		);
+	}
+
+	/// Records a structured `value` under `label` for json mode and the ledger. Display mode
+	/// leaves printing it to the caller.
+	pub fn json_field(&mut self, label: &str, value: impl Serialize) -> Result<()> {
+		self.fields.insert(key(label), serde_json::to_value(value)?);
+
+		Ok(())
+	}
+
+	/// Prints a line of diagnostic output, to stderr in json mode so stdout only ever carries
+	/// the json object.
+	pub fn line(&self, line: impl Display) {
+		match self.format {
+			OutputFormat::Display => println!("{}", line),
+			OutputFormat::Json => eprintln!("{}", line),
+		}
+	}
+
+	/// Records the program and base64 instruction data of each instruction for json mode and the
+	/// ledger, display mode prints nothing.
+	pub fn instructions(&mut self, instructions: &[Instruction]) {
+		let instructions = instructions
+			.iter()
+			.map(|instruction| {
+				json!({
+					"program_id": instruction.program_id.to_string(),
+					"data": BASE64_STANDARD.encode(&instruction.data),
+				})
+			})
+			.collect();
+
+		self.fields
+			.insert("instructions".to_string(), Value::Array(instructions));
+	}
+
+	/// Appends the report to the ledger with `err` recorded under `error`, so a command that
+	/// fails partway, such as a batch with failed rows, still leaves a record of what it sent.
+	/// Returns `err` for the caller to propagate.
+	pub fn fail(mut self, err: Error) -> Error {
+		if let Some((ref path, ref command)) = self.ledger {
+			self.fields
+				.insert("error".to_string(), Value::String(format!("{:#}", err)));
+			if let Err(ledger_err) = self.append_to_ledger(path, command) {
+				eprintln!("Warning: {}", ledger_err);
+			}
+		}
+
+		err
+	}
+
+	/// Writes a single line with one `write` call so records from concurrent runs never
+	/// interleave.
+	fn append_to_ledger(&self, path: &str, command: &str) -> Result<()> {
+		let mut record = Map::new();
+		record.insert(
+			"timestamp".to_string(),
+			Value::String(Utc::now().to_rfc3339()),
+		);
+		record.insert("command".to_string(), Value::String(command.to_string()));
+		record.extend(self.fields.clone());
+
+		let mut line = serde_json::to_string(&record)?;
+		line.push('\n');
+
+		OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(path)
+			.and_then(|mut file| file.write_all(line.as_bytes()))
+			.map_err(|err| anyhow!("Unable to append to output file {}: {}", path, err))
+	}
+
+	/// Returns the fields selected for the json output, failing on a selected field the command
+	/// did not report.
+	fn selected_fields(&self) -> Result<Map<String, Value>> {
+		if self.selected.is_empty() {
+			return Ok(self.fields.clone());
+		}
+
+		self.selected
+			.iter()
+			.map(|key| match self.fields.get(key) {
+				Some(value) => Ok((key.clone(), value.clone())),
+				None => Err(anyhow!(
+					"Unknown output field `{}`, expected one of: {}",
+					key,
+					self.fields
+						.keys()
+						.map(String::as_str)
+						.collect::<Vec<_>>()
+						.join(", ")
+				)),
+			})
+			.collect()
+	}
+
+	/// Prints the collected JSON object, narrowed to the selected fields, display mode has
+	/// already printed everything, and appends the whole report to the ledger.
+	pub fn finish(self) -> Result<()> {
+		if let Some((ref path, ref command)) = self.ledger {
+			self.append_to_ledger(path, command)?;
+		}
+
+		if self.is_json() {
+			println!(
+				"{}",
+				serde_json::to_string_pretty(&self.selected_fields()?)?
+			);
+		}
+
+		Ok(())
+	}
+}
+
+/// Returns the json key of `label`, its snake-cased form.
+fn key(label: &str) -> String {
+	label.to_lowercase().replace(' ', "_")
+}
+
+/// Prints `err` as a JSON object carrying the `CliError` code when there is one.
+pub fn print_error(err: &Error) -> Result<()> {
+	let code = err
+		.downcast_ref::<CliError>()
+		.map(CliError::code)
+		.unwrap_or("error");
+
+	let error = json!({
+		"error": {
+			"code": code,
+			"message": format!("{:#}", err),
+		}
+	});
+	println!("{}", serde_json::to_string_pretty(&error)?);
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn report(selected: &[&str]) -> Report {
+		let selected: Vec<String> = selected.iter().map(|field| field.to_string()).collect();
+		let mut report = Report::new(OutputFormat::Json).select(&selected);
+		report.field("Signature", "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnb");
+		report.field("Slot", 42);
+		report.field("Amount", 100);
+		report
+	}
+
+	#[test]
+	fn selected_fields_narrow_the_output() {
+		let selected = report(&["signature", "Slot"]).selected_fields().unwrap();
+		assert_eq!(selected.keys().collect::<Vec<_>>(), ["signature", "slot"]);
+		assert_eq!(selected["slot"], "42");
+	}
+
+	#[test]
+	fn no_selection_keeps_every_field() {
+		assert_eq!(report(&[]).selected_fields().unwrap().len(), 3);
+	}
+
+	#[test]
+	fn unknown_fields_are_rejected() {
+		let err = report(&["signature", "fee"]).selected_fields().unwrap_err();
+		assert!(err.to_string().contains("`fee`"));
+		assert!(err.to_string().contains("amount, signature, slot"));
+	}
+}
@@ -0,0 +1,67 @@
+use solana_program::pubkey::Pubkey;
+
+const TOKENIZER_SEED: &[u8] = b"tokenizer";
+const PRINCIPAL_MINT_SEED: &[u8] = b"principal";
+const YIELD_MINT_SEED: &[u8] = b"yield";
+
+/// Derives the tokenizer of `underlying_mint` expiring at `expiry_date` under `program_id`. The
+/// tokenizer crate's own helpers always derive under its compiled-in program id.
+pub fn get_tokenizer_address(
+	underlying_mint: &Pubkey,
+	expiry_date: i64,
+	program_id: &Pubkey,
+) -> (Pubkey, u8) {
+	Pubkey::find_program_address(
+		&[
+			TOKENIZER_SEED,
+			underlying_mint.as_ref(),
+			&expiry_date.to_le_bytes(),
+		],
+		program_id,
+	)
+}
+
+pub fn get_principal_mint_address(tokenizer: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+	Pubkey::find_program_address(&[PRINCIPAL_MINT_SEED, tokenizer.as_ref()], program_id)
+}
+
+pub fn get_yield_mint_address(tokenizer: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+	Pubkey::find_program_address(&[YIELD_MINT_SEED, tokenizer.as_ref()], program_id)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn derivations_match_the_tokenizer_crate() {
+		let program_id = sclr_token::id();
+		let underlying_mint = Pubkey::new_unique();
+		let expiry_date = 1_767_139_200;
+
+		let tokenizer = get_tokenizer_address(&underlying_mint, expiry_date, &program_id);
+		assert_eq!(
+			tokenizer,
+			sclr_token::get_tokenizer_address(&underlying_mint, expiry_date)
+		);
+		assert_eq!(
+			get_principal_mint_address(&tokenizer.0, &program_id),
+			sclr_token::get_principal_mint_address(&tokenizer.0)
+		);
+		assert_eq!(
+			get_yield_mint_address(&tokenizer.0, &program_id),
+			sclr_token::get_yield_mint_address(&tokenizer.0)
+		);
+	}
+
+	#[test]
+	fn derivations_follow_the_program_id() {
+		let underlying_mint = Pubkey::new_unique();
+		let program_id = Pubkey::new_unique();
+
+		assert_ne!(
+			get_tokenizer_address(&underlying_mint, 0, &program_id),
+			get_tokenizer_address(&underlying_mint, 0, &sclr_token::id())
+		);
+	}
+}
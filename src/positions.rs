@@ -0,0 +1,179 @@
+use {
+	crate::{
+		output::Report,
+		pda::{get_principal_mint_address, get_yield_mint_address},
+		rpc::RpcProvider,
+		state,
+	},
+	anyhow::{anyhow, Result},
+	serde::Serialize,
+	solana_account_decoder::UiAccountData,
+	solana_client::rpc_request::TokenAccountsFilter,
+	solana_program::{program_option::COption, program_pack::Pack, pubkey::Pubkey},
+	spl_token::state::Mint,
+	std::{collections::BTreeMap, str::FromStr},
+};
+
+/// The most accounts `getMultipleAccounts` returns in a single request.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Default, Serialize)]
+struct Position {
+	tokenizer: String,
+	principal_mint: Option<String>,
+	principal_amount: Option<String>,
+	yield_mint: Option<String>,
+	yield_amount: Option<String>,
+}
+
+enum PositionKind {
+	Principal,
+	Yield,
+}
+
+/// Matches a mint to its tokenizer by treating the mint authority as the candidate tokenizer
+/// and re-deriving the principal and yield mint addresses from it.
+fn classify_mint(mint_address: &Pubkey, mint: &Mint) -> Option<(Pubkey, PositionKind)> {
+	let COption::Some(tokenizer_address) = mint.mint_authority else {
+		return None;
+	};
+
+	if get_principal_mint_address(&tokenizer_address, &crate::program_id()).0 == *mint_address {
+		Some((tokenizer_address, PositionKind::Principal))
+	} else if get_yield_mint_address(&tokenizer_address, &crate::program_id()).0 == *mint_address {
+		Some((tokenizer_address, PositionKind::Yield))
+	} else {
+		None
+	}
+}
+
+/// Reports every principal and yield token balance held by `owner`, grouped by tokenizer.
+pub fn positions(client: &impl RpcProvider, owner: &Pubkey, report: &mut Report) -> Result<()> {
+	let token_accounts = client
+		.get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(spl_token::id()))
+		.map_err(|err| anyhow!("Unable to fetch token accounts of {}: {}", owner, err))?;
+
+	let balances: Vec<(Pubkey, String)> = token_accounts
+		.into_iter()
+		.filter_map(|keyed_account| {
+			let UiAccountData::Json(parsed_account) = keyed_account.account.data else {
+				return None;
+			};
+
+			let info = &parsed_account.parsed["info"];
+			let mint = Pubkey::from_str(info["mint"].as_str()?).ok()?;
+			let amount = info["tokenAmount"]["uiAmountString"].as_str()?.to_string();
+
+			Some((mint, amount))
+		})
+		.collect();
+
+	let mint_addresses: Vec<Pubkey> = balances.iter().map(|(mint, _)| *mint).collect();
+	let mut mint_accounts = Vec::with_capacity(mint_addresses.len());
+	for chunk in mint_addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+		mint_accounts.extend(
+			client
+				.get_multiple_accounts(chunk)
+				.map_err(|err| anyhow!("Unable to fetch mint accounts: {}", err))?,
+		);
+	}
+
+	let mut positions: BTreeMap<Pubkey, Position> = BTreeMap::new();
+	for ((mint_address, amount), mint_account) in balances.into_iter().zip(mint_accounts) {
+		let Some(mint) = mint_account.and_then(|account| Mint::unpack(&account.data).ok()) else {
+			continue;
+		};
+
+		let Some((tokenizer_address, kind)) = classify_mint(&mint_address, &mint) else {
+			continue;
+		};
+
+		let position = positions
+			.entry(tokenizer_address)
+			.or_insert_with(|| Position {
+				tokenizer: tokenizer_address.to_string(),
+				..Position::default()
+			});
+
+		match kind {
+			PositionKind::Principal => {
+				position.principal_mint = Some(mint_address.to_string());
+				position.principal_amount = Some(amount);
+			}
+			PositionKind::Yield => {
+				position.yield_mint = Some(mint_address.to_string());
+				position.yield_amount = Some(amount);
+			}
+		}
+	}
+
+	report.json_field("Positions", positions.values().collect::<Vec<_>>())?;
+	if report.is_json() {
+		return Ok(());
+	}
+
+	if positions.is_empty() {
+		println!("No principal or yield positions found for {}", owner);
+	}
+
+	for position in positions.values() {
+		println!("Tokenizer Address: {}", position.tokenizer);
+		println!(
+			"  Principal: {}",
+			position.principal_amount.as_deref().unwrap_or("0")
+		);
+		println!(
+			"  Yield: {}",
+			position.yield_amount.as_deref().unwrap_or("0")
+		);
+	}
+
+	Ok(())
+}
+
+/// Returns the UI balance of `token_account`, or zero when the account does not exist.
+fn ui_balance(client: &impl RpcProvider, token_account: &Pubkey) -> Result<String> {
+	let account = client
+		.get_account_with_commitment(token_account, client.commitment())
+		.map_err(|err| anyhow!("Unable to fetch token account {}: {}", token_account, err))?
+		.value;
+
+	if account.is_none() {
+		return Ok("0".to_string());
+	}
+
+	client
+		.get_token_account_balance(token_account)
+		.map(|balance| balance.ui_amount_string)
+		.map_err(|err| anyhow!("Unable to fetch balance of {}: {}", token_account, err))
+}
+
+/// Reports the underlying, principal and yield balances `owner` holds for one tokenizer.
+pub fn balances(
+	client: &impl RpcProvider,
+	owner: &Pubkey,
+	tokenizer_address: &Pubkey,
+	report: &mut Report,
+) -> Result<()> {
+	let underlying_mint = state::fetch_underlying_mint(client, tokenizer_address)?;
+	let (principal_mint, _) = get_principal_mint_address(tokenizer_address, &crate::program_id());
+	let (yield_mint, _) = get_yield_mint_address(tokenizer_address, &crate::program_id());
+
+	let underlying_token_program = crate::mint_token_program(client, &underlying_mint, false)?;
+
+	for (label, mint, token_program) in [
+		("Underlying", underlying_mint, underlying_token_program),
+		("Principal", principal_mint, spl_token::id()),
+		("Yield", yield_mint, spl_token::id()),
+	] {
+		let token_account =
+			spl_associated_token_account::get_associated_token_address_with_program_id(
+				owner,
+				&mint,
+				&token_program,
+			);
+		report.field(label, ui_balance(client, &token_account)?);
+	}
+
+	Ok(())
+}
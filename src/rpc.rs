@@ -0,0 +1,587 @@
+use {
+	solana_account_decoder::parse_token::UiTokenAmount,
+	solana_client::{
+		client_error::Result as ClientResult,
+		rpc_client::RpcClient,
+		rpc_config::{
+			RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSendTransactionConfig,
+			RpcSimulateTransactionConfig,
+		},
+		rpc_request::TokenAccountsFilter,
+		rpc_response::{RpcKeyedAccount, RpcResult, RpcSimulateTransactionResult},
+	},
+	solana_program::{clock::Slot, message::Message, pubkey::Pubkey},
+	solana_sdk::{
+		account::Account, clock::UnixTimestamp, commitment_config::CommitmentConfig, hash::Hash,
+		signature::Signature, transaction::Transaction,
+	},
+	solana_transaction_status::{
+		EncodedConfirmedTransactionWithStatusMeta, TransactionStatus, UiTransactionEncoding,
+	},
+};
+
+/// The RPC methods the CLI goes through, so commands can run against something other than a
+/// live cluster. Each mirrors the `RpcClient` method of the same name.
+pub trait RpcProvider {
+	fn url(&self) -> String;
+
+	fn commitment(&self) -> CommitmentConfig;
+
+	fn get_latest_blockhash(&self) -> ClientResult<Hash>;
+
+	fn get_slot(&self) -> ClientResult<Slot>;
+
+	fn get_block_time(&self, slot: Slot) -> ClientResult<UnixTimestamp>;
+
+	fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account>;
+
+	fn get_account_data(&self, pubkey: &Pubkey) -> ClientResult<Vec<u8>>;
+
+	fn get_account_with_commitment(
+		&self,
+		pubkey: &Pubkey,
+		commitment_config: CommitmentConfig,
+	) -> RpcResult<Option<Account>>;
+
+	fn get_account_with_config(
+		&self,
+		pubkey: &Pubkey,
+		config: RpcAccountInfoConfig,
+	) -> RpcResult<Option<Account>>;
+
+	fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> ClientResult<Vec<Option<Account>>>;
+
+	fn get_program_accounts_with_config(
+		&self,
+		pubkey: &Pubkey,
+		config: RpcProgramAccountsConfig,
+	) -> ClientResult<Vec<(Pubkey, Account)>>;
+
+	fn get_token_account_balance(&self, pubkey: &Pubkey) -> ClientResult<UiTokenAmount>;
+
+	fn get_token_accounts_by_owner(
+		&self,
+		owner: &Pubkey,
+		token_account_filter: TokenAccountsFilter,
+	) -> ClientResult<Vec<RpcKeyedAccount>>;
+
+	fn get_token_supply(&self, mint: &Pubkey) -> ClientResult<UiTokenAmount>;
+
+	fn get_fee_for_message(&self, message: &Message) -> ClientResult<u64>;
+
+	fn get_signature_statuses(
+		&self,
+		signatures: &[Signature],
+	) -> RpcResult<Vec<Option<TransactionStatus>>>;
+
+	fn get_transaction(
+		&self,
+		signature: &Signature,
+		encoding: UiTransactionEncoding,
+	) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta>;
+
+	fn simulate_transaction_with_config(
+		&self,
+		transaction: &Transaction,
+		config: RpcSimulateTransactionConfig,
+	) -> RpcResult<RpcSimulateTransactionResult>;
+
+	fn send_transaction_with_config(
+		&self,
+		transaction: &Transaction,
+		config: RpcSendTransactionConfig,
+	) -> ClientResult<Signature>;
+
+	fn send_and_confirm_transaction(&self, transaction: &Transaction) -> ClientResult<Signature>;
+
+	fn send_and_confirm_transaction_with_spinner(
+		&self,
+		transaction: &Transaction,
+	) -> ClientResult<Signature>;
+
+	fn confirm_transaction_with_spinner(
+		&self,
+		signature: &Signature,
+		recent_blockhash: &Hash,
+		commitment_config: CommitmentConfig,
+	) -> ClientResult<()>;
+
+	fn poll_for_signature(&self, signature: &Signature) -> ClientResult<()>;
+}
+
+impl RpcProvider for RpcClient {
+	fn url(&self) -> String {
+		RpcClient::url(self)
+	}
+
+	fn commitment(&self) -> CommitmentConfig {
+		RpcClient::commitment(self)
+	}
+
+	fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+		RpcClient::get_latest_blockhash(self)
+	}
+
+	fn get_slot(&self) -> ClientResult<Slot> {
+		RpcClient::get_slot(self)
+	}
+
+	fn get_block_time(&self, slot: Slot) -> ClientResult<UnixTimestamp> {
+		RpcClient::get_block_time(self, slot)
+	}
+
+	fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account> {
+		RpcClient::get_account(self, pubkey)
+	}
+
+	fn get_account_data(&self, pubkey: &Pubkey) -> ClientResult<Vec<u8>> {
+		RpcClient::get_account_data(self, pubkey)
+	}
+
+	fn get_account_with_commitment(
+		&self,
+		pubkey: &Pubkey,
+		commitment_config: CommitmentConfig,
+	) -> RpcResult<Option<Account>> {
+		RpcClient::get_account_with_commitment(self, pubkey, commitment_config)
+	}
+
+	fn get_account_with_config(
+		&self,
+		pubkey: &Pubkey,
+		config: RpcAccountInfoConfig,
+	) -> RpcResult<Option<Account>> {
+		RpcClient::get_account_with_config(self, pubkey, config)
+	}
+
+	fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> ClientResult<Vec<Option<Account>>> {
+		RpcClient::get_multiple_accounts(self, pubkeys)
+	}
+
+	fn get_program_accounts_with_config(
+		&self,
+		pubkey: &Pubkey,
+		config: RpcProgramAccountsConfig,
+	) -> ClientResult<Vec<(Pubkey, Account)>> {
+		RpcClient::get_program_accounts_with_config(self, pubkey, config)
+	}
+
+	fn get_token_account_balance(&self, pubkey: &Pubkey) -> ClientResult<UiTokenAmount> {
+		RpcClient::get_token_account_balance(self, pubkey)
+	}
+
+	fn get_token_accounts_by_owner(
+		&self,
+		owner: &Pubkey,
+		token_account_filter: TokenAccountsFilter,
+	) -> ClientResult<Vec<RpcKeyedAccount>> {
+		RpcClient::get_token_accounts_by_owner(self, owner, token_account_filter)
+	}
+
+	fn get_token_supply(&self, mint: &Pubkey) -> ClientResult<UiTokenAmount> {
+		RpcClient::get_token_supply(self, mint)
+	}
+
+	fn get_fee_for_message(&self, message: &Message) -> ClientResult<u64> {
+		RpcClient::get_fee_for_message(self, message)
+	}
+
+	fn get_signature_statuses(
+		&self,
+		signatures: &[Signature],
+	) -> RpcResult<Vec<Option<TransactionStatus>>> {
+		RpcClient::get_signature_statuses(self, signatures)
+	}
+
+	fn get_transaction(
+		&self,
+		signature: &Signature,
+		encoding: UiTransactionEncoding,
+	) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta> {
+		RpcClient::get_transaction(self, signature, encoding)
+	}
+
+	fn simulate_transaction_with_config(
+		&self,
+		transaction: &Transaction,
+		config: RpcSimulateTransactionConfig,
+	) -> RpcResult<RpcSimulateTransactionResult> {
+		RpcClient::simulate_transaction_with_config(self, transaction, config)
+	}
+
+	fn send_transaction_with_config(
+		&self,
+		transaction: &Transaction,
+		config: RpcSendTransactionConfig,
+	) -> ClientResult<Signature> {
+		RpcClient::send_transaction_with_config(self, transaction, config)
+	}
+
+	fn send_and_confirm_transaction(&self, transaction: &Transaction) -> ClientResult<Signature> {
+		RpcClient::send_and_confirm_transaction(self, transaction)
+	}
+
+	fn send_and_confirm_transaction_with_spinner(
+		&self,
+		transaction: &Transaction,
+	) -> ClientResult<Signature> {
+		RpcClient::send_and_confirm_transaction_with_spinner(self, transaction)
+	}
+
+	fn confirm_transaction_with_spinner(
+		&self,
+		signature: &Signature,
+		recent_blockhash: &Hash,
+		commitment_config: CommitmentConfig,
+	) -> ClientResult<()> {
+		RpcClient::confirm_transaction_with_spinner(
+			self,
+			signature,
+			recent_blockhash,
+			commitment_config,
+		)
+	}
+
+	fn poll_for_signature(&self, signature: &Signature) -> ClientResult<()> {
+		RpcClient::poll_for_signature(self, signature)
+	}
+}
+
+#[cfg(test)]
+pub mod mock {
+	use {
+		super::*,
+		async_trait::async_trait,
+		serde_json::Value,
+		solana_client::{
+			client_error::{ClientError, ClientErrorKind},
+			rpc_request::RpcRequest,
+			rpc_response::{Response, RpcResponseContext},
+		},
+		solana_program::program_pack::Pack,
+		solana_rpc_client::rpc_sender::{RpcSender, RpcTransportStats},
+		solana_transaction_status::TransactionConfirmationStatus,
+		std::{
+			collections::HashMap,
+			sync::{
+				atomic::{AtomicUsize, Ordering},
+				Arc, Mutex,
+			},
+		},
+	};
+
+	/// An in-memory `RpcProvider`. Accounts are served from `accounts` and every sent
+	/// transaction is recorded in `sent` and confirms immediately.
+	#[derive(Default)]
+	pub struct MockRpc {
+		pub accounts: HashMap<Pubkey, Account>,
+		pub sent: Mutex<Vec<Transaction>>,
+	}
+
+	impl MockRpc {
+		/// Slot every sent transaction lands in, distinct from the slot responses are served at.
+		pub const LANDED_SLOT: Slot = 7;
+
+		/// Adds a legacy token account of `mint` owned by `owner` holding `amount`.
+		pub fn with_token_account(
+			mut self,
+			address: Pubkey,
+			mint: Pubkey,
+			owner: Pubkey,
+			amount: u64,
+		) -> Self {
+			let mut data = vec![0; spl_token::state::Account::LEN];
+			spl_token::state::Account::pack(
+				spl_token::state::Account {
+					mint,
+					owner,
+					amount,
+					state: spl_token::state::AccountState::Initialized,
+					..spl_token::state::Account::default()
+				},
+				&mut data,
+			)
+			.expect("token account fits its own length");
+
+			self.accounts.insert(
+				address,
+				Account {
+					lamports: 2_039_280,
+					data,
+					owner: spl_token::id(),
+					executable: false,
+					rent_epoch: 0,
+				},
+			);
+			self
+		}
+
+		/// Adds a legacy mint account with `decimals`.
+		pub fn with_mint(mut self, address: Pubkey, decimals: u8) -> Self {
+			let mut data = vec![0; spl_token::state::Mint::LEN];
+			spl_token::state::Mint::pack(
+				spl_token::state::Mint {
+					decimals,
+					is_initialized: true,
+					..spl_token::state::Mint::default()
+				},
+				&mut data,
+			)
+			.expect("mint fits its own length");
+
+			self.accounts.insert(
+				address,
+				Account {
+					lamports: 1_461_600,
+					data,
+					owner: spl_token::id(),
+					executable: false,
+					rent_epoch: 0,
+				},
+			);
+			self
+		}
+
+		fn unsupported<T>(method: &str) -> ClientResult<T> {
+			Err(ClientError::from(ClientErrorKind::Custom(format!(
+				"`{}` is not supported by the mock RPC",
+				method
+			))))
+		}
+
+		fn respond<T>(value: T) -> RpcResult<T> {
+			Ok(Response {
+				context: RpcResponseContext::new(1),
+				value,
+			})
+		}
+
+		fn record(&self, transaction: &Transaction) -> ClientResult<Signature> {
+			self.sent
+				.lock()
+				.expect("no test panics while recording")
+				.push(transaction.clone());
+			Ok(transaction.signatures[0])
+		}
+	}
+
+	/// An RPC endpoint answering every request with the result of `respond`, for exercising
+	/// senders that wrap several endpoints.
+	pub struct MockSender {
+		url: String,
+		respond: fn() -> ClientResult<Value>,
+		requests: Arc<AtomicUsize>,
+	}
+
+	impl MockSender {
+		pub fn new(url: &str, respond: fn() -> ClientResult<Value>) -> Self {
+			Self {
+				url: url.to_string(),
+				respond,
+				requests: Arc::default(),
+			}
+		}
+
+		/// Counts the requests the endpoint receives, readable once it is owned by a client.
+		pub fn requests(&self) -> Arc<AtomicUsize> {
+			Arc::clone(&self.requests)
+		}
+	}
+
+	#[async_trait]
+	impl RpcSender for MockSender {
+		async fn send(&self, _request: RpcRequest, _params: Value) -> ClientResult<Value> {
+			self.requests.fetch_add(1, Ordering::Relaxed);
+			(self.respond)()
+		}
+
+		fn get_transport_stats(&self) -> RpcTransportStats {
+			RpcTransportStats::default()
+		}
+
+		fn url(&self) -> String {
+			self.url.clone()
+		}
+	}
+
+	impl RpcProvider for MockRpc {
+		fn url(&self) -> String {
+			"http://mock".to_string()
+		}
+
+		fn commitment(&self) -> CommitmentConfig {
+			CommitmentConfig::confirmed()
+		}
+
+		fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+			Ok(Hash::default())
+		}
+
+		fn get_slot(&self) -> ClientResult<Slot> {
+			Ok(1)
+		}
+
+		fn get_block_time(&self, _slot: Slot) -> ClientResult<UnixTimestamp> {
+			Self::unsupported("get_block_time")
+		}
+
+		fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account> {
+			self.accounts.get(pubkey).cloned().ok_or_else(|| {
+				ClientError::from(ClientErrorKind::Custom(format!(
+					"AccountNotFound: pubkey={}",
+					pubkey
+				)))
+			})
+		}
+
+		fn get_account_data(&self, pubkey: &Pubkey) -> ClientResult<Vec<u8>> {
+			self.get_account(pubkey).map(|account| account.data)
+		}
+
+		fn get_account_with_commitment(
+			&self,
+			pubkey: &Pubkey,
+			_commitment_config: CommitmentConfig,
+		) -> RpcResult<Option<Account>> {
+			Self::respond(self.accounts.get(pubkey).cloned())
+		}
+
+		fn get_account_with_config(
+			&self,
+			pubkey: &Pubkey,
+			_config: RpcAccountInfoConfig,
+		) -> RpcResult<Option<Account>> {
+			Self::respond(self.accounts.get(pubkey).cloned())
+		}
+
+		fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> ClientResult<Vec<Option<Account>>> {
+			Ok(pubkeys
+				.iter()
+				.map(|pubkey| self.accounts.get(pubkey).cloned())
+				.collect())
+		}
+
+		fn get_program_accounts_with_config(
+			&self,
+			pubkey: &Pubkey,
+			_config: RpcProgramAccountsConfig,
+		) -> ClientResult<Vec<(Pubkey, Account)>> {
+			Ok(self
+				.accounts
+				.iter()
+				.filter(|(_, account)| account.owner == *pubkey)
+				.map(|(address, account)| (*address, account.clone()))
+				.collect())
+		}
+
+		fn get_token_account_balance(&self, pubkey: &Pubkey) -> ClientResult<UiTokenAmount> {
+			let account = self.get_account(pubkey)?;
+			let amount = spl_token::state::Account::unpack(&account.data)
+				.map_err(|err| ClientError::from(ClientErrorKind::Custom(err.to_string())))?
+				.amount;
+
+			Ok(UiTokenAmount {
+				ui_amount: None,
+				decimals: 0,
+				amount: amount.to_string(),
+				ui_amount_string: amount.to_string(),
+			})
+		}
+
+		fn get_token_accounts_by_owner(
+			&self,
+			_owner: &Pubkey,
+			_token_account_filter: TokenAccountsFilter,
+		) -> ClientResult<Vec<RpcKeyedAccount>> {
+			Ok(Vec::new())
+		}
+
+		fn get_token_supply(&self, mint: &Pubkey) -> ClientResult<UiTokenAmount> {
+			let account = self.get_account(mint)?;
+			let mint = spl_token::state::Mint::unpack(&account.data)
+				.map_err(|err| ClientError::from(ClientErrorKind::Custom(err.to_string())))?;
+
+			Ok(UiTokenAmount {
+				ui_amount: None,
+				decimals: mint.decimals,
+				amount: mint.supply.to_string(),
+				ui_amount_string: mint.supply.to_string(),
+			})
+		}
+
+		fn get_fee_for_message(&self, _message: &Message) -> ClientResult<u64> {
+			Ok(5_000)
+		}
+
+		fn get_signature_statuses(
+			&self,
+			signatures: &[Signature],
+		) -> RpcResult<Vec<Option<TransactionStatus>>> {
+			Self::respond(
+				signatures
+					.iter()
+					.map(|_| {
+						Some(TransactionStatus {
+							slot: Self::LANDED_SLOT,
+							confirmations: None,
+							status: Ok(()),
+							err: None,
+							confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+						})
+					})
+					.collect(),
+			)
+		}
+
+		fn get_transaction(
+			&self,
+			_signature: &Signature,
+			_encoding: UiTransactionEncoding,
+		) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta> {
+			Self::unsupported("get_transaction")
+		}
+
+		fn simulate_transaction_with_config(
+			&self,
+			_transaction: &Transaction,
+			_config: RpcSimulateTransactionConfig,
+		) -> RpcResult<RpcSimulateTransactionResult> {
+			Self::unsupported("simulate_transaction_with_config")
+		}
+
+		fn send_transaction_with_config(
+			&self,
+			transaction: &Transaction,
+			_config: RpcSendTransactionConfig,
+		) -> ClientResult<Signature> {
+			self.record(transaction)
+		}
+
+		fn send_and_confirm_transaction(
+			&self,
+			transaction: &Transaction,
+		) -> ClientResult<Signature> {
+			self.record(transaction)
+		}
+
+		fn send_and_confirm_transaction_with_spinner(
+			&self,
+			transaction: &Transaction,
+		) -> ClientResult<Signature> {
+			self.record(transaction)
+		}
+
+		fn confirm_transaction_with_spinner(
+			&self,
+			_signature: &Signature,
+			_recent_blockhash: &Hash,
+			_commitment_config: CommitmentConfig,
+		) -> ClientResult<()> {
+			Ok(())
+		}
+
+		fn poll_for_signature(&self, _signature: &Signature) -> ClientResult<()> {
+			Ok(())
+		}
+	}
+}
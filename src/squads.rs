@@ -0,0 +1,337 @@
+use {
+	crate::{output::Report, rpc::RpcProvider},
+	anyhow::{anyhow, Result},
+	borsh::BorshSerialize,
+	solana_program::{
+		hash::hash,
+		instruction::{AccountMeta, Instruction},
+		message::Message,
+		pubkey::Pubkey,
+		system_program,
+	},
+};
+
+solana_program::declare_id!("SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf");
+
+const SEED_PREFIX: &[u8] = b"multisig";
+const SEED_VAULT: &[u8] = b"vault";
+const SEED_TRANSACTION: &[u8] = b"transaction";
+const SEED_PROPOSAL: &[u8] = b"proposal";
+
+/// Offset of `transaction_index` in the multisig account: discriminator, create key,
+/// config authority, threshold and time lock precede it.
+const TRANSACTION_INDEX_OFFSET: usize = 8 + 32 + 32 + 2 + 4;
+
+#[derive(BorshSerialize)]
+struct VaultTransactionCreateArgs {
+	vault_index: u8,
+	ephemeral_signers: u8,
+	transaction_message: Vec<u8>,
+	memo: Option<String>,
+}
+
+#[derive(BorshSerialize)]
+struct ProposalCreateArgs {
+	transaction_index: u64,
+	draft: bool,
+}
+
+pub fn get_vault_address(multisig: &Pubkey, vault_index: u8) -> (Pubkey, u8) {
+	Pubkey::find_program_address(
+		&[SEED_PREFIX, multisig.as_ref(), SEED_VAULT, &[vault_index]],
+		&id(),
+	)
+}
+
+fn get_transaction_address(multisig: &Pubkey, transaction_index: u64) -> (Pubkey, u8) {
+	Pubkey::find_program_address(
+		&[
+			SEED_PREFIX,
+			multisig.as_ref(),
+			SEED_TRANSACTION,
+			&transaction_index.to_le_bytes(),
+		],
+		&id(),
+	)
+}
+
+fn get_proposal_address(multisig: &Pubkey, transaction_index: u64) -> (Pubkey, u8) {
+	Pubkey::find_program_address(
+		&[
+			SEED_PREFIX,
+			multisig.as_ref(),
+			SEED_TRANSACTION,
+			&transaction_index.to_le_bytes(),
+			SEED_PROPOSAL,
+		],
+		&id(),
+	)
+}
+
+/// Anchor instruction discriminator: the first 8 bytes of `sha256("global:<name>")`.
+fn discriminator(name: &str) -> [u8; 8] {
+	let mut discriminator = [0u8; 8];
+	discriminator.copy_from_slice(&hash(format!("global:{}", name).as_bytes()).to_bytes()[..8]);
+	discriminator
+}
+
+fn instruction_data<T: BorshSerialize>(name: &str, args: &T) -> Result<Vec<u8>> {
+	let mut data = discriminator(name).to_vec();
+	args.serialize(&mut data)?;
+	Ok(data)
+}
+
+/// Serializes `instructions` in the Squads `TransactionMessage` format, which uses `u8`
+/// length prefixes everywhere except for instruction data, which uses `u16`.
+fn serialize_transaction_message(vault: &Pubkey, instructions: &[Instruction]) -> Result<Vec<u8>> {
+	let message = Message::new(instructions, Some(vault));
+	let header = message.header;
+	let num_accounts = message.account_keys.len();
+
+	let mut buffer = vec![
+		header.num_required_signatures,
+		header.num_required_signatures - header.num_readonly_signed_accounts,
+		(num_accounts
+			- header.num_required_signatures as usize
+			- header.num_readonly_unsigned_accounts as usize) as u8,
+	];
+
+	buffer.push(u8::try_from(num_accounts).map_err(|_| anyhow!("Too many accounts"))?);
+	for account_key in &message.account_keys {
+		buffer.extend_from_slice(account_key.as_ref());
+	}
+
+	buffer.push(
+		u8::try_from(message.instructions.len()).map_err(|_| anyhow!("Too many instructions"))?,
+	);
+	for instruction in &message.instructions {
+		buffer.push(instruction.program_id_index);
+		buffer.push(
+			u8::try_from(instruction.accounts.len())
+				.map_err(|_| anyhow!("Too many instruction accounts"))?,
+		);
+		buffer.extend_from_slice(&instruction.accounts);
+		buffer.extend_from_slice(
+			&u16::try_from(instruction.data.len())
+				.map_err(|_| anyhow!("Instruction data too large"))?
+				.to_le_bytes(),
+		);
+		buffer.extend_from_slice(&instruction.data);
+	}
+
+	// No address lookup tables
+	buffer.push(0);
+
+	Ok(buffer)
+}
+
+/// Wraps `instructions` into a vault transaction on `multisig` and opens a proposal for it,
+/// returning the instructions to be signed and sent by `creator`.
+pub fn create_proposal(
+	client: &impl RpcProvider,
+	multisig: &Pubkey,
+	creator: &Pubkey,
+	instructions: &[Instruction],
+	report: &mut Report,
+) -> Result<Vec<Instruction>> {
+	let multisig_data = client
+		.get_account_data(multisig)
+		.map_err(|err| anyhow!("Unable to fetch multisig account {}: {}", multisig, err))?;
+
+	let transaction_index = multisig_data
+		.get(TRANSACTION_INDEX_OFFSET..TRANSACTION_INDEX_OFFSET + 8)
+		.and_then(|bytes| bytes.try_into().ok())
+		.map(u64::from_le_bytes)
+		.ok_or_else(|| anyhow!("Account {} is not a Squads multisig", multisig))?
+		+ 1;
+
+	let (vault_address, _) = get_vault_address(multisig, 0);
+	let (transaction_address, _) = get_transaction_address(multisig, transaction_index);
+	let (proposal_address, _) = get_proposal_address(multisig, transaction_index);
+
+	report.field("Squads Vault Address", vault_address);
+	report.field("Squads Transaction Address", transaction_address);
+	report.field("Squads Proposal Address", proposal_address);
+
+	let vault_transaction_create = Instruction {
+		program_id: id(),
+		accounts: vec![
+			AccountMeta::new(*multisig, false),
+			AccountMeta::new(transaction_address, false),
+			AccountMeta::new_readonly(*creator, true),
+			AccountMeta::new(*creator, true),
+			AccountMeta::new_readonly(system_program::id(), false),
+		],
+		data: instruction_data(
+			"vault_transaction_create",
+			&VaultTransactionCreateArgs {
+				vault_index: 0,
+				ephemeral_signers: 0,
+				transaction_message: serialize_transaction_message(&vault_address, instructions)?,
+				memo: None,
+			},
+		)?,
+	};
+
+	let proposal_create = Instruction {
+		program_id: id(),
+		accounts: vec![
+			AccountMeta::new_readonly(*multisig, false),
+			AccountMeta::new(proposal_address, false),
+			AccountMeta::new_readonly(*creator, true),
+			AccountMeta::new(*creator, true),
+			AccountMeta::new_readonly(system_program::id(), false),
+		],
+		data: instruction_data(
+			"proposal_create",
+			&ProposalCreateArgs {
+				transaction_index,
+				draft: false,
+			},
+		)?,
+	};
+
+	Ok(vec![vault_transaction_create, proposal_create])
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		crate::{output::OutputFormat, rpc::mock::MockRpc},
+		solana_sdk::account::Account,
+	};
+
+	/// The fields of a Squads v4 `Multisig` account, in account order.
+	#[derive(BorshSerialize)]
+	struct MultisigFixture {
+		discriminator: [u8; 8],
+		create_key: Pubkey,
+		config_authority: Pubkey,
+		threshold: u16,
+		time_lock: u32,
+		transaction_index: u64,
+		stale_transaction_index: u64,
+		rent_collector: Option<Pubkey>,
+		bump: u8,
+		members: Vec<(Pubkey, u8)>,
+	}
+
+	fn multisig_account(transaction_index: u64) -> Account {
+		let mut discriminator = [0u8; 8];
+		discriminator.copy_from_slice(&hash(b"account:Multisig").to_bytes()[..8]);
+
+		Account {
+			lamports: 1_000_000,
+			data: borsh::to_vec(&MultisigFixture {
+				discriminator,
+				create_key: Pubkey::new_unique(),
+				config_authority: Pubkey::default(),
+				threshold: 2,
+				time_lock: 0,
+				transaction_index,
+				stale_transaction_index: 3,
+				rent_collector: None,
+				bump: 255,
+				members: vec![(Pubkey::new_unique(), 7), (Pubkey::new_unique(), 7)],
+			})
+			.unwrap(),
+			owner: id(),
+			executable: false,
+			rent_epoch: 0,
+		}
+	}
+
+	#[test]
+	fn proposal_targets_the_next_transaction_index() {
+		let multisig = Pubkey::new_unique();
+		let mut client = MockRpc::default();
+		client.accounts.insert(multisig, multisig_account(41));
+
+		let instructions = create_proposal(
+			&client,
+			&multisig,
+			&Pubkey::new_unique(),
+			&[],
+			&mut Report::new(OutputFormat::Json),
+		)
+		.unwrap();
+
+		assert_eq!(
+			instructions[0].accounts[1].pubkey,
+			get_transaction_address(&multisig, 42).0
+		);
+		assert_eq!(
+			instructions[1].accounts[1].pubkey,
+			get_proposal_address(&multisig, 42).0
+		);
+		assert_eq!(
+			instructions[1].data[8..],
+			[42u64.to_le_bytes().as_slice(), &[0]].concat()
+		);
+	}
+
+	#[test]
+	fn short_account_is_not_a_multisig() {
+		let multisig = Pubkey::new_unique();
+		let mut client = MockRpc::default();
+		let mut account = multisig_account(41);
+		account.data.truncate(TRANSACTION_INDEX_OFFSET + 4);
+		client.accounts.insert(multisig, account);
+
+		assert!(create_proposal(
+			&client,
+			&multisig,
+			&Pubkey::new_unique(),
+			&[],
+			&mut Report::new(OutputFormat::Json),
+		)
+		.is_err());
+	}
+
+	#[test]
+	fn transaction_message_uses_squads_length_prefixes() {
+		let vault = Pubkey::new_unique();
+		let program = Pubkey::new_unique();
+		let writable = Pubkey::new_unique();
+		let readonly = Pubkey::new_unique();
+		// Longer than a `u8` length prefix could describe
+		let data = vec![9u8; 300];
+
+		let buffer = serialize_transaction_message(
+			&vault,
+			&[Instruction {
+				program_id: program,
+				accounts: vec![
+					AccountMeta::new(writable, false),
+					AccountMeta::new_readonly(readonly, false),
+				],
+				data: data.clone(),
+			}],
+		)
+		.unwrap();
+
+		// Signers, writable signers and writable non-signers
+		assert_eq!(buffer[..3], [1, 1, 1]);
+
+		assert_eq!(buffer[3], 4);
+		let keys: Vec<Pubkey> = buffer[4..4 + 4 * 32]
+			.chunks(32)
+			.map(|key| Pubkey::try_from(key).unwrap())
+			.collect();
+		assert_eq!(keys[0], vault);
+		assert_eq!(keys[1], writable);
+
+		let instructions = &buffer[4 + 4 * 32..];
+		assert_eq!(instructions[0], 1);
+		assert_eq!(keys[instructions[1] as usize], program);
+		assert_eq!(instructions[2], 2);
+		assert_eq!(keys[instructions[3] as usize], writable);
+		assert_eq!(keys[instructions[4] as usize], readonly);
+		assert_eq!(u16::from_le_bytes([instructions[5], instructions[6]]), 300);
+		assert_eq!(instructions[7..7 + 300], data);
+
+		// No address lookup tables, and nothing after them
+		assert_eq!(instructions[7 + 300..], [0]);
+	}
+}
@@ -0,0 +1,343 @@
+use {
+	crate::{
+		error::CliError,
+		output::Report,
+		pda::{get_principal_mint_address, get_tokenizer_address, get_yield_mint_address},
+		rpc::RpcProvider,
+		TimeSource,
+	},
+	anyhow::{anyhow, Result},
+	borsh::BorshDeserialize,
+	chrono::DateTime,
+	sclr_token::state::Tokenizer,
+	serde::Serialize,
+	solana_account_decoder::UiAccountEncoding,
+	solana_client::{
+		client_error::ClientError,
+		rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+		rpc_filter::{Memcmp, RpcFilterType},
+	},
+	solana_program::pubkey::Pubkey,
+	std::{thread, time::Duration},
+};
+
+/// Fetches and deserializes the tokenizer account at `tokenizer_address`.
+pub fn fetch_tokenizer(
+	client: &impl RpcProvider,
+	tokenizer_address: &Pubkey,
+) -> Result<Tokenizer, CliError> {
+	let account = client
+		.get_account_with_commitment(tokenizer_address, client.commitment())?
+		.value
+		.ok_or(CliError::AccountNotFound(*tokenizer_address))?;
+
+	if account.owner != crate::program_id() {
+		return Err(CliError::WrongOwner(*tokenizer_address));
+	}
+
+	Tokenizer::deserialize(&mut account.data.as_slice())
+		.map_err(|err| CliError::InvalidTokenizer(*tokenizer_address, err))
+}
+
+/// Reads the underlying mint recorded in the tokenizer account at `tokenizer_address`.
+pub fn fetch_underlying_mint(
+	client: &impl RpcProvider,
+	tokenizer_address: &Pubkey,
+) -> Result<Pubkey, CliError> {
+	match fetch_tokenizer(client, tokenizer_address) {
+		Ok(tokenizer) => Ok(tokenizer.underlying_mint),
+		Err(CliError::AccountNotFound(_)) => {
+			Err(CliError::MissingUnderlyingMint(*tokenizer_address))
+		}
+		Err(err) => Err(err),
+	}
+}
+
+/// Reports the mints, expiry and vault balance of the tokenizer at `tokenizer_address`.
+pub fn status(
+	client: &impl RpcProvider,
+	tokenizer_address: &Pubkey,
+	report: &mut Report,
+) -> Result<()> {
+	let tokenizer = fetch_tokenizer(client, tokenizer_address)?;
+
+	let expiry = DateTime::from_timestamp(tokenizer.expiry_date, 0)
+		.map(|expiry| expiry.to_rfc3339())
+		.unwrap_or_else(|| tokenizer.expiry_date.to_string());
+
+	let underlying_token_program =
+		crate::mint_token_program(client, &tokenizer.underlying_mint, false)?;
+	let underlying_vault_address =
+		spl_associated_token_account::get_associated_token_address_with_program_id(
+			tokenizer_address,
+			&tokenizer.underlying_mint,
+			&underlying_token_program,
+		);
+
+	let deposited = client
+		.get_token_account_balance(&underlying_vault_address)
+		.map_err(|err| {
+			anyhow!(
+				"Unable to fetch vault balance of {}: {}",
+				underlying_vault_address,
+				err
+			)
+		})?;
+
+	report.field("Tokenizer Address", tokenizer_address);
+	report.field("Underlying Mint Address", tokenizer.underlying_mint);
+	report.field("Expiry", expiry);
+	report.field(
+		"Principal Mint Address",
+		get_principal_mint_address(tokenizer_address, &crate::program_id()).0,
+	);
+	report.field(
+		"Yield Mint Address",
+		get_yield_mint_address(tokenizer_address, &crate::program_id()).0,
+	);
+	report.field("Vault Address", underlying_vault_address);
+	report.field("Total Deposited", deposited.ui_amount_string);
+
+	Ok(())
+}
+
+/// Longest wait between polls of `watch` while the RPC node rate limits it, unless the poll
+/// interval itself is longer.
+const MAX_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(300);
+
+fn format_duration(seconds: i64) -> String {
+	format!(
+		"{}d {}h {}m {}s",
+		seconds / 86_400,
+		seconds % 86_400 / 3_600,
+		seconds % 3_600 / 60,
+		seconds % 60
+	)
+}
+
+/// Reports the vault balance and time to expiry of the tokenizer at `tokenizer_address` every
+/// `interval`, stopping once the tokenizer has expired according to `time_source`. Each poll is
+/// reported on its own, as a line in display mode and a JSON object in json mode. A poll reads
+/// the time and the balance concurrently when `max_in_flight` allows more than one read at once.
+/// Polls the RPC node rate limits are retried after a wait doubling up to
+/// `MAX_RATE_LIMIT_BACKOFF`.
+pub fn watch(
+	client: &(impl RpcProvider + Sync),
+	tokenizer_address: &Pubkey,
+	interval: Duration,
+	max_in_flight: u64,
+	time_source: &TimeSource,
+	new_report: impl Fn() -> Report,
+) -> Result<()> {
+	let tokenizer = fetch_tokenizer(client, tokenizer_address)?;
+	let underlying_token_program =
+		crate::mint_token_program(client, &tokenizer.underlying_mint, false)?;
+	let underlying_vault_address =
+		spl_associated_token_account::get_associated_token_address_with_program_id(
+			tokenizer_address,
+			&tokenizer.underlying_mint,
+			&underlying_token_program,
+		);
+
+	let mut backoff = interval;
+	loop {
+		let read_time = || time_source.now(client);
+		let read_balance = || client.get_token_account_balance(&underlying_vault_address);
+		let (now, deposited) = if max_in_flight > 1 {
+			thread::scope(|scope| {
+				let now = scope.spawn(read_time);
+				let deposited = read_balance();
+				(
+					now.join().expect("reading the time does not panic"),
+					deposited,
+				)
+			})
+		} else {
+			(read_time(), read_balance())
+		};
+
+		let rate_limited = now.as_ref().is_err_and(|err| {
+			err.downcast_ref::<ClientError>()
+				.is_some_and(crate::is_rate_limited)
+		}) || deposited.as_ref().is_err_and(crate::is_rate_limited);
+		if rate_limited {
+			backoff = backoff
+				.saturating_mul(2)
+				.min(MAX_RATE_LIMIT_BACKOFF.max(interval));
+			eprintln!(
+				"Warning: rate limited by the RPC node, polling again in {}s",
+				backoff.as_secs()
+			);
+			thread::sleep(backoff);
+			continue;
+		}
+		backoff = interval;
+
+		let now = now.map_err(|err| anyhow!("Unable to fetch cluster time: {}", err))?;
+		let deposited = deposited.map_err(|err| {
+			anyhow!(
+				"Unable to fetch vault balance of {}: {}",
+				underlying_vault_address,
+				err
+			)
+		})?;
+
+		let time = DateTime::from_timestamp(now, 0)
+			.map(|time| time.to_rfc3339())
+			.unwrap_or_else(|| now.to_string());
+
+		let expires_in =
+			(now < tokenizer.expiry_date).then(|| format_duration(tokenizer.expiry_date - now));
+
+		let mut report = new_report();
+		report.json_field("Time", &time)?;
+		report.json_field("Total Deposited", &deposited.ui_amount_string)?;
+		report.json_field("Expires In", &expires_in)?;
+		if !report.is_json() {
+			println!(
+				"{} Total Deposited: {} {}",
+				time,
+				deposited.ui_amount_string,
+				match expires_in {
+					Some(ref expires_in) => format!("Expires In: {}", expires_in),
+					None => "Expired".to_string(),
+				}
+			);
+		}
+		report.finish()?;
+
+		if expires_in.is_none() {
+			return Ok(());
+		}
+
+		thread::sleep(interval);
+	}
+}
+
+/// Listing more markets than this without `--limit` prints a warning to narrow the search.
+const LARGE_MARKET_COUNT: usize = 100;
+
+/// Size of a borsh-serialized tokenizer account: the underlying mint followed by the expiry date.
+const TOKENIZER_ACCOUNT_LEN: u64 = 32 + 8;
+
+/// Offset of the underlying mint within a tokenizer account.
+const UNDERLYING_MINT_OFFSET: usize = 0;
+
+#[derive(Serialize)]
+struct Market {
+	tokenizer: String,
+	underlying_mint: String,
+	expiry: String,
+}
+
+/// Lists every tokenizer of the program, or only those of `underlying_mint`, ordered by expiry.
+/// Program accounts only count as tokenizers when they deserialize as one and sit at the address
+/// derived from their own underlying mint and expiry. `offset` and `limit` page through the
+/// ordered list.
+pub fn list_markets(
+	client: &impl RpcProvider,
+	underlying_mint: Option<&Pubkey>,
+	offset: usize,
+	limit: Option<usize>,
+	report: &mut Report,
+) -> Result<()> {
+	let mut filters = vec![RpcFilterType::DataSize(TOKENIZER_ACCOUNT_LEN)];
+	if let Some(mint) = underlying_mint {
+		filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+			UNDERLYING_MINT_OFFSET,
+			mint.to_bytes().to_vec(),
+		)));
+	}
+
+	let accounts = client
+		.get_program_accounts_with_config(
+			&crate::program_id(),
+			RpcProgramAccountsConfig {
+				filters: Some(filters),
+				account_config: RpcAccountInfoConfig {
+					encoding: Some(UiAccountEncoding::Base64),
+					..RpcAccountInfoConfig::default()
+				},
+				..RpcProgramAccountsConfig::default()
+			},
+		)
+		.map_err(|err| anyhow!("Unable to fetch tokenizer program accounts: {}", err))?;
+
+	let mut tokenizers: Vec<(Pubkey, Tokenizer)> = accounts
+		.into_iter()
+		.filter_map(|(address, account)| {
+			let tokenizer = Tokenizer::deserialize(&mut account.data.as_slice()).ok()?;
+			let (expected_address, _) = get_tokenizer_address(
+				&tokenizer.underlying_mint,
+				tokenizer.expiry_date,
+				&crate::program_id(),
+			);
+
+			(address == expected_address).then_some((address, tokenizer))
+		})
+		.collect();
+	tokenizers.sort_by_key(|(_, tokenizer)| tokenizer.expiry_date);
+
+	if limit.is_none() && tokenizers.len() > LARGE_MARKET_COUNT {
+		eprintln!(
+			"Warning: found {} markets, pass `--underlying-mint` or `--limit` to narrow the list",
+			tokenizers.len()
+		);
+	}
+
+	let markets: Vec<Market> = tokenizers
+		.iter()
+		.skip(offset)
+		.take(limit.unwrap_or(usize::MAX))
+		.map(|(address, tokenizer)| Market {
+			tokenizer: address.to_string(),
+			underlying_mint: tokenizer.underlying_mint.to_string(),
+			expiry: DateTime::from_timestamp(tokenizer.expiry_date, 0)
+				.map(|expiry| expiry.to_rfc3339())
+				.unwrap_or_else(|| tokenizer.expiry_date.to_string()),
+		})
+		.collect();
+
+	report.json_field("Markets", &markets)?;
+	if report.is_json() {
+		return Ok(());
+	}
+
+	if markets.is_empty() {
+		println!("No markets found");
+	}
+
+	for market in &markets {
+		println!("Tokenizer Address: {}", market.tokenizer);
+		println!("  Underlying Mint: {}", market.underlying_mint);
+		println!("  Expiry: {}", market.expiry);
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn tokenizer_account(underlying_mint: &Pubkey, expiry_date: i64) -> Vec<u8> {
+		let mut data = vec![0; TOKENIZER_ACCOUNT_LEN as usize];
+		data[UNDERLYING_MINT_OFFSET..UNDERLYING_MINT_OFFSET + 32]
+			.copy_from_slice(&underlying_mint.to_bytes());
+		data[32..].copy_from_slice(&expiry_date.to_le_bytes());
+		data
+	}
+
+	#[test]
+	fn filters_match_the_tokenizer_layout() {
+		let underlying_mint = Pubkey::new_unique();
+		let data = tokenizer_account(&underlying_mint, 1_767_139_200);
+
+		// `try_from_slice` rejects trailing bytes, so this pins the account length exactly
+		let tokenizer = Tokenizer::try_from_slice(&data).unwrap();
+		assert_eq!(tokenizer.underlying_mint, underlying_mint);
+		assert_eq!(tokenizer.expiry_date, 1_767_139_200);
+
+		assert!(Tokenizer::try_from_slice(&data[..data.len() - 1]).is_err());
+	}
+}